@@ -0,0 +1,84 @@
+//! Plays a WAV file through the audio analyser and prints the reacting strips as text.
+//!
+//! Run with `cargo run --release --example audio_wav --features std -- music.wav`.
+
+use smart_led_effects::{
+    audio::{wav::WavReader, Analyser},
+    strip::{Breathe, EffectIterator, Fire, Spectrum, Strobe},
+    RGB8,
+};
+use std::{env, fs::File, io::BufReader};
+
+const COUNT: usize = 48;
+const BANDS: usize = 12;
+/// Samples per frame, 21 ms at 48 kHz.
+const FRAME: usize = 1024;
+
+/// One character per pixel, by brightness.
+fn show(pixels: &[RGB8]) -> String {
+    const SHADES: &[u8] = b" .:-=+*#%@";
+    pixels
+        .iter()
+        .map(|p| {
+            let max = p.r.max(p.g).max(p.b) as usize;
+            SHADES[max * (SHADES.len() - 1) / 255] as char
+        })
+        .collect()
+}
+
+fn main() -> std::io::Result<()> {
+    let Some(path) = env::args().nth(1) else {
+        eprintln!("usage: audio_wav <file.wav>");
+        return Ok(());
+    };
+    let mut wav = WavReader::new(BufReader::new(File::open(path)?))?;
+    println!("{} Hz, {} channel(s)", wav.sample_rate(), wav.channels());
+
+    let mut analyser = Analyser::<BANDS, FRAME>::new(wav.sample_rate(), None, None);
+    let mut spectrum = Spectrum::<COUNT, BANDS>::new(None, None);
+    let mut bass = Breathe::<COUNT>::white(Some(0.05));
+    bass.set_free_running(false);
+    let mut strobe = Strobe::<COUNT>::seeded(1, None, 0, Some(0.2));
+    strobe.set_automatic(false);
+    let mut fire = Fire::<COUNT>::seeded(1, None, None);
+
+    let frame_ms = (FRAME as u64 * 1000 / wav.sample_rate() as u64) as u32;
+    let mut samples = [0.0; FRAME];
+    let mut pixels = [RGB8::default(); COUNT];
+    let mut beats = 0;
+    let mut time_ms = 0;
+    loop {
+        let count = wav.read_mono(&mut samples)?;
+        if count == 0 {
+            break;
+        }
+        if !analyser.push_f32(&samples[..count]) {
+            continue;
+        }
+        let beat = analyser.take_beat();
+        beats += beat as u32;
+        spectrum.set_spectrum(analyser.spectrum());
+        bass.pulse(analyser.bass() * 4.0);
+        if beat {
+            strobe.trigger();
+        }
+        fire.set_intensity(analyser.level() * 3.0);
+
+        spectrum.next_line(&mut pixels, frame_ms);
+        let spectrum_line = show(&pixels);
+        bass.next_line(&mut pixels, frame_ms);
+        let bass_line = show(&pixels[..8]);
+        strobe.next_line(&mut pixels, frame_ms);
+        let strobe_line = show(&pixels[..8]);
+        fire.next_line(&mut pixels, frame_ms);
+        let fire_line = show(&pixels[..16]);
+        println!(
+            "{:>6.2}s |{spectrum_line}| bass |{bass_line}| beat |{strobe_line}| fire |{fire_line}| rms {:.3}",
+            time_ms as f32 / 1000.0,
+            analyser.level(),
+        );
+        time_ms += frame_ms;
+    }
+    println!("{beats} beats in {:.1}s", time_ms as f32 / 1000.0);
+    Ok(())
+}
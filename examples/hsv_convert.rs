@@ -0,0 +1,71 @@
+//! Compares per-pixel and batched HSV to RGB8 conversion on a 1000-LED frame.
+//!
+//! Run with `cargo run --release --example hsv_convert --features std`.
+
+use palette::Hsv;
+use smart_led_effects::{
+    strip::{EffectIterator, Rainbow},
+    utils::{hsv_slice_to_rgb8, hsv_to_rgb8_pixel, HUE_LUT},
+    RGB8,
+};
+use std::{hint::black_box, time::Instant};
+
+const COUNT: usize = 1000;
+const FRAMES: u32 = 2000;
+
+fn time(label: &str, mut frame: impl FnMut()) {
+    let start = Instant::now();
+    for _ in 0..FRAMES {
+        frame();
+    }
+    let per_frame = start.elapsed() / FRAMES;
+    println!("{label:<28} {:>8.1} us/frame", per_frame.as_secs_f64() * 1e6);
+}
+
+fn main() {
+    let src: Vec<Hsv> = (0..COUNT)
+        .map(|i| Hsv::new(i as f32 * 360.0 / COUNT as f32, 0.8, 0.6))
+        .collect();
+    let mut dst = [RGB8::default(); COUNT];
+
+    let max_error = |dst: &[RGB8]| {
+        src.iter()
+            .zip(dst)
+            .map(|(hsv, out)| {
+                let exact = hsv_to_rgb8_pixel(*hsv);
+                [
+                    exact.r.abs_diff(out.r),
+                    exact.g.abs_diff(out.g),
+                    exact.b.abs_diff(out.b),
+                ]
+                .into_iter()
+                .max()
+                .unwrap_or(0)
+            })
+            .max()
+            .unwrap_or(0)
+    };
+
+    time("per pixel (palette)", || {
+        for (out, hsv) in dst.iter_mut().zip(&src) {
+            *out = hsv_to_rgb8_pixel(black_box(*hsv));
+        }
+        black_box(&dst);
+    });
+    time("hsv_slice_to_rgb8", || {
+        hsv_slice_to_rgb8(black_box(&src), &mut dst, None);
+        black_box(&dst);
+    });
+    println!("{:<28} max error {}", "", max_error(&dst));
+    time("hsv_slice_to_rgb8 + HUE_LUT", || {
+        hsv_slice_to_rgb8(black_box(&src), &mut dst, Some(&HUE_LUT));
+        black_box(&dst);
+    });
+    println!("{:<28} max error {}", "", max_error(&dst));
+
+    let mut rainbow = Rainbow::<COUNT>::new(None);
+    time("Rainbow::next_line", || {
+        rainbow.next_line(&mut dst, 16);
+        black_box(&dst);
+    });
+}
@@ -0,0 +1,265 @@
+//! Audio analysis for music-reactive effects.
+//!
+//! The caller pushes mono PCM samples (mix stereo down first) into an [Analyser] as they
+//! arrive from the I2S/ADC driver. Every block of samples it updates the RMS level, a `B`-band
+//! spectrum and an onset (beat) flag. Everything is fixed size, no allocation.
+//!
+//! The results are plain numbers to hand to effects, e.g. [crate::strip::Spectrum],
+//! [crate::strip::Breathe::pulse], [crate::strip::Strobe::trigger] or
+//! [crate::strip::Fire::set_intensity].
+//!
+//! ```rust
+//! use smart_led_effects::audio::Analyser;
+//!
+//! let rate = 16_000;
+//! let mut analyser = Analyser::<8>::new(rate, None, None);
+//! // one block of a full scale 1 kHz tone
+//! let tone: Vec<f32> = (0..1024)
+//!     .map(|i| (i as f32 * 1000.0 * core::f32::consts::TAU / rate as f32).sin())
+//!     .collect();
+//! assert!(analyser.push_f32(&tone));
+//!
+//! assert!((analyser.level() - core::f32::consts::FRAC_1_SQRT_2).abs() < 0.01);
+//! let loudest = (0..8).max_by(|a, b| analyser.spectrum()[*a].total_cmp(&analyser.spectrum()[*b]));
+//! let band = loudest.unwrap();
+//! assert!(analyser.band_frequency(band) > 700.0 && analyser.band_frequency(band) < 1400.0);
+//! ```
+
+#[cfg(feature = "std")]
+pub mod wav;
+
+/// Hann window generated sample by sample with the cosine recurrence
+/// `cos((n + 1)θ) = 2cos(θ)cos(nθ) - cos((n - 1)θ)`, so no table is needed.
+struct Window {
+    coeff: f32,
+    cos: f32,
+    previous: f32,
+}
+
+impl Window {
+    fn new(len: usize) -> Self {
+        let coeff = 2.0 * libm::cosf(core::f32::consts::TAU / len as f32);
+        Self { coeff, cos: 1.0, previous: coeff / 2.0 }
+    }
+
+    fn next(&mut self) -> f32 {
+        let weight = 0.5 - 0.5 * self.cos;
+        let cos = self.coeff * self.cos - self.previous;
+        self.previous = self.cos;
+        self.cos = cos;
+        weight
+    }
+}
+
+/// In-place iterative radix-2 FFT. `re.len()` must be a power of two.
+fn fft(re: &mut [f32], im: &mut [f32]) {
+    let n = re.len();
+    let mut j = 0;
+    for i in 1..n {
+        let mut bit = n >> 1;
+        while j & bit != 0 {
+            j ^= bit;
+            bit >>= 1;
+        }
+        j |= bit;
+        if i < j {
+            re.swap(i, j);
+            im.swap(i, j);
+        }
+    }
+
+    let mut len = 2;
+    while len <= n {
+        let angle = -core::f32::consts::TAU / len as f32;
+        let (step_im, step_re) = (libm::sinf(angle), libm::cosf(angle));
+        for start in (0..n).step_by(len) {
+            let (mut w_re, mut w_im) = (1.0, 0.0);
+            for a in start..start + len / 2 {
+                let b = a + len / 2;
+                let t_re = re[b] * w_re - im[b] * w_im;
+                let t_im = re[b] * w_im + im[b] * w_re;
+                re[b] = re[a] - t_re;
+                im[b] = im[a] - t_im;
+                re[a] += t_re;
+                im[a] += t_im;
+                let next = w_re * step_re - w_im * step_im;
+                w_im = w_re * step_im + w_im * step_re;
+                w_re = next;
+            }
+        }
+        len <<= 1;
+    }
+}
+
+/// Block based level, spectrum and beat analysis of a mono sample stream.
+///
+/// Every `L` samples (a power of two, 1024 by default) the block is Hann windowed and
+/// transformed with an FFT, and the bins are summed into `B` bands spaced logarithmically
+/// between the minimum and maximum frequency. The buffers take `8 * L` bytes.
+pub struct Analyser<const B: usize, const L: usize = 1024> {
+    real: [f32; L],
+    imag: [f32; L],
+    count: usize,
+    bins: [(usize, usize); B],
+    frequencies: [f32; B],
+    level: f32,
+    spectrum: [f32; B],
+    flux_mean: f32,
+    sensitivity: f32,
+    refractory_blocks: u32,
+    blocks_since_beat: u32,
+    beat: bool,
+}
+
+impl<const B: usize, const L: usize> Analyser<B, L> {
+    const DEFAULT_MIN_FREQUENCY: f32 = 60.0;
+    const DEFAULT_MAX_FREQUENCY: f32 = 8000.0;
+    const DEFAULT_SENSITIVITY: f32 = 1.5;
+    /// Shortest time between two beats, in seconds.
+    const REFRACTORY: f32 = 0.1;
+    /// Weight of the newest block in the running mean of the spectral flux.
+    const FLUX_ALPHA: f32 = 0.1;
+    /// Flux below this never counts as a beat, so silence does not trigger.
+    const FLUX_FLOOR: f32 = 0.002;
+
+    /// The bands cover 60 Hz to 8 kHz by default, capped at the Nyquist frequency.
+    ///
+    /// # Panics
+    ///
+    /// If `L` is not a power of two.
+    pub fn new(sample_rate: u32, min_frequency: Option<f32>, max_frequency: Option<f32>) -> Self {
+        assert!(L.is_power_of_two(), "block length must be a power of two");
+        let rate = sample_rate as f32;
+        let hz_per_bin = rate / L as f32;
+        let min = min_frequency.unwrap_or(Self::DEFAULT_MIN_FREQUENCY).max(hz_per_bin / 2.0);
+        let max = max_frequency.unwrap_or(Self::DEFAULT_MAX_FREQUENCY).min(rate / 2.0).max(min);
+        let ratio = libm::powf(max / min, 1.0 / B.max(1) as f32);
+
+        let mut bins = [(0, 0); B];
+        let mut frequencies = [0.0; B];
+        let mut low = min;
+        for (band, frequency) in bins.iter_mut().zip(frequencies.iter_mut()) {
+            let high = low * ratio;
+            *frequency = libm::sqrtf(low * high);
+            let first = libm::roundf(low / hz_per_bin) as usize;
+            let last = libm::roundf(high / hz_per_bin) as usize;
+            // narrow bass bands still get the bin nearest their centre
+            let centre = libm::roundf(*frequency / hz_per_bin) as usize;
+            *band = if last > first { (first, last) } else { (centre, centre + 1) };
+            band.1 = band.1.min(L / 2);
+            band.0 = band.0.min(band.1.saturating_sub(1));
+            low = high;
+        }
+
+        let block_secs = L as f32 / rate;
+        Self {
+            real: [0.0; L],
+            imag: [0.0; L],
+            count: 0,
+            bins,
+            frequencies,
+            level: 0.0,
+            spectrum: [0.0; B],
+            flux_mean: 0.0,
+            sensitivity: Self::DEFAULT_SENSITIVITY,
+            refractory_blocks: libm::ceilf(Self::REFRACTORY / block_secs) as u32,
+            blocks_since_beat: u32::MAX,
+            beat: false,
+        }
+    }
+
+    /// Push samples in `-1.0..=1.0`. Returns true if at least one block completed.
+    pub fn push_f32(&mut self, samples: &[f32]) -> bool {
+        let mut completed = false;
+        for &sample in samples {
+            completed |= self.push_sample(sample);
+        }
+        completed
+    }
+
+    /// Push signed 16-bit samples. Returns true if at least one block completed.
+    pub fn push_i16(&mut self, samples: &[i16]) -> bool {
+        let mut completed = false;
+        for &sample in samples {
+            completed |= self.push_sample(sample as f32 / 32768.0);
+        }
+        completed
+    }
+
+    fn push_sample(&mut self, sample: f32) -> bool {
+        self.real[self.count] = sample;
+        self.count += 1;
+        if self.count < L {
+            return false;
+        }
+        self.finish_block();
+        true
+    }
+
+    fn finish_block(&mut self) {
+        self.count = 0;
+        let n = L as f32;
+        let sum_squares: f32 = self.real.iter().map(|s| s * s).sum();
+        self.level = libm::sqrtf(sum_squares / n);
+
+        let mut window = Window::new(L);
+        for sample in self.real.iter_mut() {
+            *sample *= window.next();
+        }
+        self.imag = [0.0; L];
+        fft(&mut self.real, &mut self.imag);
+
+        // spectral flux: how much the bands rose since the last block
+        let mut flux = 0.0;
+        for ((first, last), value) in self.bins.iter().zip(self.spectrum.iter_mut()) {
+            let power: f32 = (*first..*last)
+                .map(|k| self.real[k] * self.real[k] + self.imag[k] * self.imag[k])
+                .sum();
+            // scaled so a full scale sine reads about 1.0, the window halves the sum
+            let amplitude = 4.0 * libm::sqrtf(power) / n;
+            flux += (amplitude - *value).max(0.0);
+            *value = amplitude;
+        }
+
+        self.blocks_since_beat = self.blocks_since_beat.saturating_add(1);
+        if flux > Self::FLUX_FLOOR
+            && flux > self.flux_mean * self.sensitivity
+            && self.blocks_since_beat > self.refractory_blocks
+        {
+            self.beat = true;
+            self.blocks_since_beat = 0;
+        }
+        self.flux_mean += (flux - self.flux_mean) * Self::FLUX_ALPHA;
+    }
+
+    /// RMS level of the last block, 0 to 1 for full scale input.
+    pub fn level(&self) -> f32 {
+        self.level
+    }
+
+    /// Amplitude of each band for the last block, lowest frequency first.
+    pub fn spectrum(&self) -> &[f32; B] {
+        &self.spectrum
+    }
+
+    /// Amplitude of the lowest band.
+    pub fn bass(&self) -> f32 {
+        self.spectrum.first().copied().unwrap_or(0.0)
+    }
+
+    /// Geometric centre frequency of band `index` in Hz.
+    pub fn band_frequency(&self, index: usize) -> f32 {
+        self.frequencies[index]
+    }
+
+    /// True once for each detected beat.
+    pub fn take_beat(&mut self) -> bool {
+        core::mem::take(&mut self.beat)
+    }
+
+    /// How far the spectral flux has to rise above its running mean to count as a beat.
+    /// Lower is more sensitive. Defaults to 1.5.
+    pub fn set_sensitivity(&mut self, sensitivity: f32) {
+        self.sensitivity = sensitivity;
+    }
+}
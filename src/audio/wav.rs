@@ -0,0 +1,156 @@
+//! Minimal WAV reader for testing audio-reactive effects offline.
+//!
+//! Reads 8/16-bit integer and 32-bit float PCM, mixing all channels down to mono `f32`.
+
+use std::io::{self, Read};
+
+fn invalid(message: &'static str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Format {
+    U8,
+    I16,
+    F32,
+}
+
+impl Format {
+    fn bytes(self) -> usize {
+        match self {
+            Format::U8 => 1,
+            Format::I16 => 2,
+            Format::F32 => 4,
+        }
+    }
+
+    fn decode(self, bytes: &[u8]) -> f32 {
+        match self {
+            Format::U8 => (bytes[0] as f32 - 128.0) / 128.0,
+            Format::I16 => i16::from_le_bytes([bytes[0], bytes[1]]) as f32 / 32768.0,
+            Format::F32 => f32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]),
+        }
+    }
+}
+
+/// Streams the samples of a WAV file as mono `f32`, ready for [super::Analyser::push_f32].
+///
+/// ```rust,no_run
+/// use smart_led_effects::audio::{wav::WavReader, Analyser};
+///
+/// let file = std::io::BufReader::new(std::fs::File::open("music.wav")?);
+/// let mut wav = WavReader::new(file)?;
+/// let mut analyser = Analyser::<16>::new(wav.sample_rate(), None, None);
+/// let mut block = [0.0; 256];
+/// loop {
+///     let count = wav.read_mono(&mut block)?;
+///     if count == 0 {
+///         break;
+///     }
+///     analyser.push_f32(&block[..count]);
+/// }
+/// # Ok::<(), std::io::Error>(())
+/// ```
+pub struct WavReader<R: Read> {
+    inner: R,
+    format: Format,
+    channels: u16,
+    sample_rate: u32,
+    remaining: u64,
+}
+
+impl<R: Read> WavReader<R> {
+    /// Parse the header and position the reader at the first sample.
+    pub fn new(mut inner: R) -> io::Result<Self> {
+        let mut riff = [0; 12];
+        inner.read_exact(&mut riff)?;
+        if &riff[0..4] != b"RIFF" || &riff[8..12] != b"WAVE" {
+            return Err(invalid("not a RIFF/WAVE file"));
+        }
+
+        let mut format = None;
+        loop {
+            let mut header = [0; 8];
+            inner.read_exact(&mut header)?;
+            let size = u32::from_le_bytes([header[4], header[5], header[6], header[7]]) as u64;
+            match &header[0..4] {
+                b"fmt " => {
+                    if size < 16 {
+                        return Err(invalid("short fmt chunk"));
+                    }
+                    let mut fmt = [0; 16];
+                    inner.read_exact(&mut fmt)?;
+                    let tag = u16::from_le_bytes([fmt[0], fmt[1]]);
+                    let channels = u16::from_le_bytes([fmt[2], fmt[3]]);
+                    let rate = u32::from_le_bytes([fmt[4], fmt[5], fmt[6], fmt[7]]);
+                    let bits = u16::from_le_bytes([fmt[14], fmt[15]]);
+                    // 0xfffe is WAVE_FORMAT_EXTENSIBLE, decided by the bit depth here
+                    let sample = match (tag, bits) {
+                        (1 | 0xfffe, 8) => Format::U8,
+                        (1 | 0xfffe, 16) => Format::I16,
+                        (3 | 0xfffe, 32) => Format::F32,
+                        _ => return Err(invalid("unsupported sample format")),
+                    };
+                    if channels == 0 {
+                        return Err(invalid("no channels"));
+                    }
+                    format = Some((sample, channels, rate));
+                    skip(&mut inner, size + (size & 1) - 16)?;
+                }
+                b"data" => {
+                    let (format, channels, sample_rate) =
+                        format.ok_or_else(|| invalid("data before fmt chunk"))?;
+                    return Ok(Self {
+                        inner,
+                        format,
+                        channels,
+                        sample_rate,
+                        remaining: size,
+                    });
+                }
+                // chunks are padded to an even length
+                _ => skip(&mut inner, size + (size & 1))?,
+            }
+        }
+    }
+
+    pub fn sample_rate(&self) -> u32 {
+        self.sample_rate
+    }
+
+    pub fn channels(&self) -> u16 {
+        self.channels
+    }
+
+    /// Fill `out` with mono samples, averaging the channels of each frame.
+    /// Returns the number written, 0 at the end of the data.
+    pub fn read_mono(&mut self, out: &mut [f32]) -> io::Result<usize> {
+        let sample_bytes = self.format.bytes();
+        let frame_bytes = sample_bytes * self.channels as usize;
+        let mut buffer = [0u8; 4];
+        let mut written = 0;
+        for slot in out.iter_mut() {
+            if self.remaining < frame_bytes as u64 {
+                break;
+            }
+            let mut sum = 0.0;
+            for _ in 0..self.channels {
+                let bytes = &mut buffer[..sample_bytes];
+                self.inner.read_exact(bytes)?;
+                sum += self.format.decode(bytes);
+            }
+            self.remaining -= frame_bytes as u64;
+            *slot = sum / self.channels as f32;
+            written += 1;
+        }
+        Ok(written)
+    }
+}
+
+fn skip(reader: &mut impl Read, bytes: u64) -> io::Result<()> {
+    let skipped = io::copy(&mut reader.take(bytes), &mut io::sink())?;
+    if skipped < bytes {
+        return Err(io::ErrorKind::UnexpectedEof.into());
+    }
+    Ok(())
+}
@@ -0,0 +1,154 @@
+//! Tempo tracking for effects that should follow the music.
+//!
+//! [BeatClock] turns a BPM (set directly or tapped in) and a downbeat into beat and bar
+//! phases, and into periods in ticks for effects such as [crate::strip::Strobe::set_period],
+//! [crate::strip::Breathe::set_period], [crate::strip::RunningLights::set_cycle_ticks] and
+//! [crate::strip::Wipe::set_duration]. All times are in milliseconds.
+//!
+//! ```rust
+//! use smart_led_effects::{beat::BeatClock, strip::Strobe};
+//!
+//! let mut clock = BeatClock::new(120.0, None);
+//! clock.tap(10_000);
+//! clock.tap(10_480);
+//! clock.tap(10_960);
+//! assert_eq!(clock.bpm(), 125.0);
+//! assert_eq!(clock.beat_in_bar(10_960), 2);
+//!
+//! let mut strobe = Strobe::<10>::seeded(1, None, 1000, None);
+//! strobe.set_period(clock.beat_ticks());
+//! ```
+//!
+//! The free functions are float counterparts of the FastLED style helpers in
+//! [crate::math8], for custom effects.
+
+/// Phase within a period of `period_us` microseconds, 0 to 1.
+fn phase(time_ms: u64, period_us: u64) -> f32 {
+    let period = period_us.max(1);
+    ((time_ms * 1000) % period) as f32 / period as f32
+}
+
+fn beat_us(bpm: f32) -> u64 {
+    ((60_000_000.0 / bpm.max(0.001)) as u64).max(1)
+}
+
+/// Sawtooth from 0 to 1, `bpm` times per minute.
+pub fn beat_phase(bpm: f32, time_ms: u64) -> f32 {
+    phase(time_ms, beat_us(bpm))
+}
+
+/// Sine wave oscillating between `low` and `high` `bpm` times per minute.
+/// `phase_offset` is in turns (0 to 1).
+pub fn beatsin(bpm: f32, low: f32, high: f32, time_ms: u64, phase_offset: f32) -> f32 {
+    let angle = (beat_phase(bpm, time_ms) + phase_offset) * core::f32::consts::TAU;
+    low + (high - low) * (0.5 + 0.5 * libm::sinf(angle))
+}
+
+/// Triangle wave between `low` and `high`, `bpm` times per minute.
+pub fn beattri(bpm: f32, low: f32, high: f32, time_ms: u64, phase_offset: f32) -> f32 {
+    let t = (beat_phase(bpm, time_ms) + phase_offset) % 1.0;
+    let ramp = if t < 0.5 { 2.0 * t } else { 2.0 - 2.0 * t };
+    low + (high - low) * ramp
+}
+
+/// Tempo and bar position, set from a BPM or by tapping.
+#[derive(Debug, Clone)]
+pub struct BeatClock {
+    beat_us: u64,
+    beats_per_bar: u32,
+    origin_ms: u64,
+    taps: [u64; Self::MAX_TAPS],
+    tap_count: usize,
+}
+
+impl BeatClock {
+    const DEFAULT_BEATS_PER_BAR: u32 = 4;
+    /// Tempo is averaged over this many taps.
+    const MAX_TAPS: usize = 8;
+    /// A pause this long between taps starts a new tempo.
+    const TAP_TIMEOUT_MS: u64 = 2000;
+
+    /// `beats_per_bar` defaults to 4. The first downbeat is at time 0.
+    pub fn new(bpm: f32, beats_per_bar: Option<u32>) -> Self {
+        Self {
+            beat_us: beat_us(bpm),
+            beats_per_bar: beats_per_bar.unwrap_or(Self::DEFAULT_BEATS_PER_BAR).max(1),
+            origin_ms: 0,
+            taps: [0; Self::MAX_TAPS],
+            tap_count: 0,
+        }
+    }
+
+    pub fn bpm(&self) -> f32 {
+        60_000_000.0 / self.beat_us as f32
+    }
+
+    /// Change the tempo. Beats are still counted from the last downbeat.
+    pub fn set_bpm(&mut self, bpm: f32) {
+        self.beat_us = beat_us(bpm);
+    }
+
+    /// Tap along with the beat. The first tap after a pause is taken as a downbeat, following
+    /// taps set the tempo from their average interval. A tap earlier than the last one, e.g.
+    /// after the caller's clock was reset, also starts again.
+    pub fn tap(&mut self, time_ms: u64) {
+        let last = self.taps[self.tap_count.saturating_sub(1)];
+        if self.tap_count == 0 || time_ms < last || time_ms - last > Self::TAP_TIMEOUT_MS {
+            self.tap_count = 0;
+            self.origin_ms = time_ms;
+        }
+        if self.tap_count == Self::MAX_TAPS {
+            self.taps.rotate_left(1);
+            self.tap_count -= 1;
+        }
+        self.taps[self.tap_count] = time_ms;
+        self.tap_count += 1;
+
+        if self.tap_count >= 2 {
+            let span = self.taps[self.tap_count - 1] - self.taps[0];
+            self.beat_us = (span * 1000 / (self.tap_count as u64 - 1)).max(1);
+        }
+    }
+
+    /// Mark `time_ms` as the first beat of a bar.
+    pub fn downbeat(&mut self, time_ms: u64) {
+        self.origin_ms = time_ms;
+    }
+
+    fn since_origin(&self, time_ms: u64) -> u64 {
+        time_ms.saturating_sub(self.origin_ms)
+    }
+
+    /// Whole beats since the last downbeat.
+    pub fn beats(&self, time_ms: u64) -> u64 {
+        self.since_origin(time_ms) * 1000 / self.beat_us
+    }
+
+    /// Position within the current beat, 0 to 1.
+    pub fn beat_phase(&self, time_ms: u64) -> f32 {
+        phase(self.since_origin(time_ms), self.beat_us)
+    }
+
+    /// Position within the current bar, 0 to 1.
+    pub fn bar_phase(&self, time_ms: u64) -> f32 {
+        phase(self.since_origin(time_ms), self.beat_us * self.beats_per_bar as u64)
+    }
+
+    /// Beat number within the bar, 0 being the downbeat.
+    pub fn beat_in_bar(&self, time_ms: u64) -> u32 {
+        (self.beats(time_ms) % self.beats_per_bar as u64) as u32
+    }
+
+    /// Length of `beats` beats in ticks (milliseconds), e.g. `0.5` for eighth notes in 4/4.
+    pub fn ticks(&self, beats: f32) -> u32 {
+        (self.beat_us as f32 * beats / 1000.0) as u32
+    }
+
+    pub fn beat_ticks(&self) -> u32 {
+        self.ticks(1.0)
+    }
+
+    pub fn bar_ticks(&self) -> u32 {
+        self.ticks(self.beats_per_bar as f32)
+    }
+}
@@ -12,23 +12,28 @@
 //! # Effects
 //! | Name | Description |
 //! | ---- | ----------- |
+//! | [strip::BarGraph] | Splits the strip into several bars showing separate values |
 //! | [strip::Bounce] | The bounce effect will generate a number of balls that bounce up and down the strip |
 //! | [strip::Breathe] | The breathe effect will generate a single colour that fades in and out |
-//! | [strip::Christmas] | (WIP) Has a green background with random red, blue and gold sparkles |
-//! | [strip::Collision] | Generates two particles that can collide and bounce or shatter |
+//! | [strip::Christmas] | Seasonal sparkles, chases, twinkling bulbs, candy-cane stripes and snowfall in themed colours |
+//! | [strip::Collision] | Particles that fly in from both ends and bounce or shatter on impact |
 //! | [strip::Cycle] | Rotates around the HSV colour space |
-//! | [strip::Cylon] | Generates the cylon eye effect |
+//! | [strip::Cylon] | Cylon/Larson scanner: one or more eyes sweep back and forth with fading trails |
 //! | [strip::Fire] | Generates an effect like a flickering flame |
-//! | [strip::Meteor] | Generates a meteor that goes down the strip trailing bits of fading debris |
+//! | [strip::Meteor] | Meteor rain: meteors fly along the strip trailing bits of fading debris |
 //! | [strip::Morse] | Converts a string into a series of dots and dashes as per Morse code formatting |
-//! | [strip::ProgressBar] | (WIP) Signals progress
+//! | [strip::PhaseTimer] | Runs a Timer through a sequence of phases, e.g. Pomodoro sessions |
+//! | [strip::ProgressBar] | Signals progress, with easing, indeterminate and error/success states |
 //! | [strip::Rainbow] | Generates a rainbow effect |
-//! | [strip::RunningLights] | Generates a running lights effect |
+//! | [strip::RunningLights] | Waves of light running along the strip in one or more colours, or a theater chase |
 //! | [strip::SnowSparkle] | Generates random sparkles |
-//! | [strip::Strobe] | Strobe light/blinder effect |
+//! | [strip::Spectrum] | Audio spectrum analyser, see [audio] |
+//! | [strip::Strobe] | Strobe light/blinder, bursts and emergency-light patterns |
 //! | [strip::Timer] | Counts down for the given duration |
 //! | [strip::Twinkle] | Generates random twinkles |
-//! | [strip::Wipe] | Generates a wipe effect |
+//! | [strip::TwinkleFox] | Every pixel twinkles on its own seeded clock, with palettes and a background |
+//! | [strip::VuMeter] | Audio level meter with peak hold and stereo centre-out layout |
+//! | [strip::Wipe] | Wipes data or sequences of colours along the strip, optionally eased and with gradients |
 //!
 //!
 //! # Example
@@ -36,33 +41,66 @@
 //! ```rust
 //! use smart_led_effects::{
 //!     strip::{self, EffectIterator},
-//!     Srgb,
+//!     RGB8,
 //! };
 //!
-//!
 //! const COUNT: usize = 55;
-//! let effect = strip::Rainbow::new(COUNT, None);
+//! let mut effect = strip::Rainbow::<COUNT>::new(None);
+//! let mut pixels = [RGB8::default(); COUNT];
 //!
-//! {
-//! let pixels = effect.next().unwrap();
-//!    
-//! // show pixels
+//! // in your frame loop
+//! effect.next_line(&mut pixels, 10);
+//! // write `pixels` via your SmartLedsWrite driver
+//! ```
 //!
-//! thread::sleep(Duration::from_millis(10));
-//! }
+//! # Pixel formats
+//!
+//! [strip::RenderAs::next_line_as] is generic over the [Pixel] trait, so the same effect can
+//! render into [RGB8], [RGB16] for 16-bit drivers, or [Srgb]/[LinSrgb] `f32` buffers for
+//! compositing before quantising.
+//!
+//! ```rust
+//! use smart_led_effects::{strip::{self, RenderAs}, RGB16};
 //!
+//! let mut effect = strip::Cycle::<8>::new(None);
+//! let mut pixels = [RGB16::default(); 8];
+//! effect.next_line_as(&mut pixels, 10);
+//! ```
+//!
+//! [strip::EffectIterator] itself renders [RGB8] and is object safe, so effects can be
+//! switched at runtime:
+//!
+//! ```rust
+//! use smart_led_effects::{strip::{self, EffectIterator}, RGB8};
+//!
+//! let mut effects: Vec<Box<dyn EffectIterator>> = vec![
+//!     Box::new(strip::Rainbow::<8>::new(None)),
+//!     Box::new(strip::Cylon::<8>::red(None, None)),
+//! ];
+//! let mut pixels = [RGB8::default(); 8];
+//! for effect in effects.iter_mut() {
+//!     effect.next_line(&mut pixels, 10);
+//! }
+//! ```
 #![cfg_attr(not(feature = "std"), no_std)]
 
 #[cfg(feature = "alloc")]
 extern crate alloc;
 
+pub mod audio;
+pub mod beat;
+pub mod math8;
+pub mod morse;
+mod pixel;
+pub mod rng;
 pub mod strip;
-mod utils;
+pub mod utils;
 
-// Public pixel type for outputs
-pub use smart_leds_trait::RGB8;
+pub use pixel::Pixel;
+// Public pixel types for outputs
+pub use smart_leds_trait::{RGB16, RGB8};
 // Re-export Srgb so consumers need not depend on palette directly.
-pub use palette::Srgb;
+pub use palette::{LinSrgb, Srgb};
 
 #[cfg(feature = "time-embassy")]
 pub mod time;
@@ -0,0 +1,117 @@
+use super::{scale8, scale8_video};
+use crate::RGB8;
+
+/// 8-bit HSV colour, FastLED's `CHSV`. `hue` 0..=255 covers the full wheel.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Hsv8 {
+    pub hue: u8,
+    pub sat: u8,
+    pub val: u8,
+}
+
+impl Hsv8 {
+    pub const fn new(hue: u8, sat: u8, val: u8) -> Self {
+        Self { hue, sat, val }
+    }
+}
+
+/// Convert to RGB with FastLED's "rainbow" mapping, which gives yellow and orange a wider
+/// band of the wheel than a mathematically even spectrum.
+///
+/// ```rust
+/// use smart_led_effects::{math8::{hsv2rgb_rainbow, Hsv8}, RGB8};
+///
+/// assert_eq!(hsv2rgb_rainbow(Hsv8::new(0, 254, 255)), RGB8::new(255, 1, 1));
+/// assert_eq!(hsv2rgb_rainbow(Hsv8::new(0, 128, 255)), RGB8::new(255, 64, 64));
+/// ```
+pub const fn hsv2rgb_rainbow(hsv: Hsv8) -> RGB8 {
+    const K255: u8 = 255;
+    const K171: u8 = 171;
+    const K170: u8 = 170;
+    const K85: u8 = 85;
+
+    let hue = hsv.hue;
+    let sat = hsv.sat;
+    let mut val = hsv.val;
+
+    let offset8 = (hue & 0x1f) << 3;
+    let third = scale8(offset8, (256u16 / 3) as u8);
+    let twothirds = scale8(offset8, ((256u16 * 2) / 3) as u8);
+
+    let (mut r, mut g, mut b) = match hue >> 5 {
+        // red -> orange
+        0 => (K255 - third, third, 0),
+        // orange -> yellow
+        1 => (K171, K85 + third, 0),
+        // yellow -> green
+        2 => (K171 - twothirds, K170 + third, 0),
+        // green -> aqua
+        3 => (0, K255 - third, third),
+        // aqua -> blue
+        4 => (0, K171 - twothirds, K85 + twothirds),
+        // blue -> purple
+        5 => (third, 0, K255 - third),
+        // purple -> pink
+        6 => (K85 + third, 0, K171 - third),
+        // pink -> red
+        _ => (K170 + third, 0, K85 - third),
+    };
+
+    if sat != 255 {
+        if sat == 0 {
+            r = 255;
+            g = 255;
+            b = 255;
+        } else {
+            let desat = scale8_video(255 - sat, 255 - sat);
+            let satscale = 255 - desat;
+            // scale8 already rounds up, so FastLED's +1 compensation is not needed
+            r = scale8(r, satscale).saturating_add(desat);
+            g = scale8(g, satscale).saturating_add(desat);
+            b = scale8(b, satscale).saturating_add(desat);
+        }
+    }
+
+    if val != 255 {
+        val = scale8_video(val, val);
+        if val == 0 {
+            r = 0;
+            g = 0;
+            b = 0;
+        } else {
+            // as FastLED with FASTLED_SCALE8_FIXED, its default
+            r = scale8(r, val);
+            g = scale8(g, val);
+            b = scale8(b, val);
+        }
+    }
+
+    RGB8 { r, g, b }
+}
+
+/// Convert to RGB with an even "spectrum" mapping (three 120 degree sections), closest to
+/// the floating-point HSV used elsewhere in the crate.
+pub const fn hsv2rgb_spectrum(hsv: Hsv8) -> RGB8 {
+    const SECTION_3: u8 = 0x40;
+
+    let hue = scale8(hsv.hue, 191);
+    let value = hsv.val;
+    let invsat = 255 - hsv.sat;
+    let brightness_floor = ((value as u16 * invsat as u16) / 256) as u8;
+    let colour_amplitude = value - brightness_floor;
+
+    let section = hue / SECTION_3;
+    let offset = hue % SECTION_3;
+    let rampup = offset;
+    let rampdown = (SECTION_3 - 1) - offset;
+
+    let rampup_adj = ((rampup as u16 * colour_amplitude as u16) / (256 / 4)) as u8 + brightness_floor;
+    let rampdown_adj =
+        ((rampdown as u16 * colour_amplitude as u16) / (256 / 4)) as u8 + brightness_floor;
+
+    match section {
+        0 => RGB8 { r: rampdown_adj, g: rampup_adj, b: brightness_floor },
+        1 => RGB8 { r: brightness_floor, g: rampdown_adj, b: rampup_adj },
+        _ => RGB8 { r: rampup_adj, g: brightness_floor, b: rampdown_adj },
+    }
+}
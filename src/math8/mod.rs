@@ -0,0 +1,167 @@
+//! 8-bit fixed-point helpers modelled on FastLED's `lib8tion`.
+//!
+//! These avoid floating point entirely and are what the integer effects (`Rainbow`, `Cycle`,
+//! `Breathe`, `BreatheRandom`, `RunningLights`, `Cylon`) use when the `int-math`
+//! feature is enabled, but they are always available for custom effects.
+//!
+//! Fractions are expressed as `u8` where `255` (or `256` for scaling) means "one".
+
+mod hsv;
+mod random;
+
+pub use hsv::{hsv2rgb_rainbow, hsv2rgb_spectrum, Hsv8};
+pub use random::Random8;
+
+use crate::RGB8;
+
+/// Scale `i` by `scale / 256`. `scale8(x, 255)` returns `x`.
+#[inline]
+pub const fn scale8(i: u8, scale: u8) -> u8 {
+    ((i as u16 * (1 + scale as u16)) >> 8) as u8
+}
+
+/// Like [scale8], but never scales a non-zero value down to zero.
+#[inline]
+pub const fn scale8_video(i: u8, scale: u8) -> u8 {
+    let j = ((i as u16 * scale as u16) >> 8) as u8;
+    if i != 0 && scale != 0 {
+        j + 1
+    } else {
+        j
+    }
+}
+
+/// Scale all three channels of `colour` by `scale / 256`.
+#[inline]
+pub const fn scale8_rgb(colour: RGB8, scale: u8) -> RGB8 {
+    RGB8 {
+        r: scale8(colour.r, scale),
+        g: scale8(colour.g, scale),
+        b: scale8(colour.b, scale),
+    }
+}
+
+/// Saturating add.
+#[inline]
+pub const fn qadd8(i: u8, j: u8) -> u8 {
+    i.saturating_add(j)
+}
+
+/// Saturating subtract.
+#[inline]
+pub const fn qsub8(i: u8, j: u8) -> u8 {
+    i.saturating_sub(j)
+}
+
+/// Linear interpolation between `a` and `b` by `frac / 256`.
+#[inline]
+pub const fn lerp8by8(a: u8, b: u8, frac: u8) -> u8 {
+    if b > a {
+        a + scale8(b - a, frac)
+    } else {
+        a - scale8(a - b, frac)
+    }
+}
+
+const B_M16_INTERLEAVE: [u8; 8] = [0, 49, 49, 41, 90, 27, 117, 10];
+
+/// Fast 8-bit sine: `theta` 0..=255 maps to one full turn, result is centred on 128.
+pub const fn sin8(theta: u8) -> u8 {
+    let mut offset = theta;
+    if theta & 0x40 != 0 {
+        offset = 255 - offset;
+    }
+    offset &= 0x3f;
+
+    let mut secoffset = offset & 0x0f;
+    if theta & 0x40 != 0 {
+        secoffset += 1;
+    }
+
+    let section = (offset >> 4) as usize;
+    let b = B_M16_INTERLEAVE[section * 2];
+    let m16 = B_M16_INTERLEAVE[section * 2 + 1];
+    let mx = ((m16 as u16 * secoffset as u16) >> 4) as u8;
+
+    let mut y = (mx + b) as i16;
+    if theta & 0x80 != 0 {
+        y = -y;
+    }
+    (y + 128) as u8
+}
+
+/// Fast 8-bit cosine, see [sin8].
+#[inline]
+pub const fn cos8(theta: u8) -> u8 {
+    sin8(theta.wrapping_add(64))
+}
+
+/// Sawtooth that wraps 0..=65535 at `bpm88` (BPM in Q8.8) beats per minute.
+#[inline]
+pub const fn beat88(bpm88: u16, time_ms: u32) -> u16 {
+    (time_ms.wrapping_mul(bpm88 as u32).wrapping_mul(280) >> 16) as u16
+}
+
+/// Sawtooth that wraps 0..=65535 `bpm` times per minute.
+#[inline]
+pub const fn beat16(bpm: u8, time_ms: u32) -> u16 {
+    beat88((bpm as u16) << 8, time_ms)
+}
+
+/// Sawtooth that wraps 0..=255 `bpm` times per minute.
+#[inline]
+pub const fn beat8(bpm: u8, time_ms: u32) -> u8 {
+    (beat16(bpm, time_ms) >> 8) as u8
+}
+
+/// Sine wave oscillating between `low` and `high` `bpm` times per minute.
+pub const fn beatsin8(bpm: u8, low: u8, high: u8, time_ms: u32, phase_offset: u8) -> u8 {
+    let beat = beat8(bpm, time_ms);
+    let beatsin = sin8(beat.wrapping_add(phase_offset));
+    let rangewidth = high.wrapping_sub(low);
+    low.wrapping_add(scale8(beatsin, rangewidth))
+}
+
+/// Quadratic ease in/out.
+pub const fn ease8_in_out_quad(i: u8) -> u8 {
+    let j = if i & 0x80 != 0 { 255 - i } else { i };
+    let jj2 = scale8(j, j) << 1;
+    if i & 0x80 != 0 {
+        255 - jj2
+    } else {
+        jj2
+    }
+}
+
+/// Cubic ease in/out.
+pub const fn ease8_in_out_cubic(i: u8) -> u8 {
+    let ii = scale8(i, i);
+    let iii = scale8(ii, i);
+    let r1 = 3 * ii as u16 - 2 * iii as u16;
+    if r1 & 0x100 != 0 {
+        255
+    } else {
+        r1 as u8
+    }
+}
+
+/// Sine of a quarter turn fraction `t` (`0..=65536` for 0 to 90 degrees), as a fraction of
+/// `65536`. Uses Bhaskara's approximation, within 0.2%.
+pub(crate) const fn quarter_sine(t: u32) -> u32 {
+    let t = if t > 1 << 16 { 1 << 16 } else { t } as u64;
+    // half a turn is 131072
+    let p = t * ((2 << 16) - t);
+    (4 * p * (1 << 16) / (5 * (1 << 32) - p)) as u32
+}
+
+/// Sinusoidal ease in/out on 16-bit fractions, `(1 - cos(πi)) / 2`.
+pub const fn ease16_in_out_sine(i: u16) -> u16 {
+    // stretch 0..=65535 to 0..=65536 so that full input eases all the way
+    let s = quarter_sine(i as u32 + (i as u32 >> 15)) as u64;
+    let eased = (s * s) >> 16;
+    if eased > u16::MAX as u64 {
+        u16::MAX
+    } else {
+        eased as u16
+    }
+}
@@ -0,0 +1,40 @@
+/// The 16-bit LCG behind FastLED's `random8`/`random16`.
+///
+/// Only suitable for visual noise; it is tiny and integer-only.
+#[derive(Debug, Clone)]
+pub struct Random8 {
+    seed: u16,
+}
+
+impl Random8 {
+    const MULTIPLIER: u16 = 2053;
+    const INCREMENT: u16 = 13849;
+
+    pub const fn new(seed: u16) -> Self {
+        Self { seed }
+    }
+
+    pub fn random16(&mut self) -> u16 {
+        self.seed = self
+            .seed
+            .wrapping_mul(Self::MULTIPLIER)
+            .wrapping_add(Self::INCREMENT);
+        self.seed
+    }
+
+    pub fn random8(&mut self) -> u8 {
+        let r = self.random16();
+        // the low byte alone has a short period, so fold in the high byte
+        (r as u8).wrapping_add((r >> 8) as u8)
+    }
+
+    /// Random value in `0..lim`.
+    pub fn random8_lim(&mut self, lim: u8) -> u8 {
+        ((self.random8() as u16 * lim as u16) >> 8) as u8
+    }
+
+    /// Random value in `min..lim`.
+    pub fn random8_range(&mut self, min: u8, lim: u8) -> u8 {
+        min + self.random8_lim(lim.saturating_sub(min))
+    }
+}
@@ -0,0 +1,188 @@
+//! Text to Morse code encoding for [crate::strip::Morse].
+//!
+//! Messages are encoded as one byte per Morse time unit, `1` for on and `0` for off, with the
+//! ITU timings: a dot is one unit, a dash three, elements of a character are separated by one
+//! unit, characters by three and words by seven.
+//!
+//! Prosigns are written between angle brackets, e.g. `"<SOS>"` or `"<AR>"`, and are sent as a
+//! single character without the gaps between letters.
+//!
+//! ```rust
+//! use smart_led_effects::morse;
+//!
+//! let mut bits = [0u8; 64];
+//! let len = morse::encode("SOS", &mut bits).unwrap();
+//! assert_eq!(&bits[..len], &[
+//!     1, 0, 1, 0, 1, 0, 0, 0,
+//!     1, 1, 1, 0, 1, 1, 1, 0, 1, 1, 1, 0, 0, 0,
+//!     1, 0, 1, 0, 1,
+//! ]);
+//! ```
+
+/// Why a message could not be encoded.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MorseError {
+    /// The output buffer is too small for the encoded message.
+    BufferFull,
+    /// The character has no Morse representation.
+    UnknownCharacter(char),
+    /// A `<` prosign was not closed with `>`.
+    UnterminatedProsign,
+}
+
+/// Units of silence between the elements of a character.
+pub const ELEMENT_GAP: usize = 1;
+/// Units of silence between characters.
+pub const LETTER_GAP: usize = 3;
+/// Units of silence between words.
+pub const WORD_GAP: usize = 7;
+
+/// Dots and dashes for a character, or None if it has no ITU representation.
+pub fn code(c: char) -> Option<&'static str> {
+    let code = match c.to_ascii_uppercase() {
+        'A' => ".-",
+        'B' => "-...",
+        'C' => "-.-.",
+        'D' => "-..",
+        'E' => ".",
+        'F' => "..-.",
+        'G' => "--.",
+        'H' => "....",
+        'I' => "..",
+        'J' => ".---",
+        'K' => "-.-",
+        'L' => ".-..",
+        'M' => "--",
+        'N' => "-.",
+        'O' => "---",
+        'P' => ".--.",
+        'Q' => "--.-",
+        'R' => ".-.",
+        'S' => "...",
+        'T' => "-",
+        'U' => "..-",
+        'V' => "...-",
+        'W' => ".--",
+        'X' => "-..-",
+        'Y' => "-.--",
+        'Z' => "--..",
+        '0' => "-----",
+        '1' => ".----",
+        '2' => "..---",
+        '3' => "...--",
+        '4' => "....-",
+        '5' => ".....",
+        '6' => "-....",
+        '7' => "--...",
+        '8' => "---..",
+        '9' => "----.",
+        '.' => ".-.-.-",
+        ',' => "--..--",
+        '?' => "..--..",
+        '\'' => ".----.",
+        '!' => "-.-.--",
+        '/' => "-..-.",
+        '(' => "-.--.",
+        ')' => "-.--.-",
+        '&' => ".-...",
+        ':' => "---...",
+        ';' => "-.-.-.",
+        '=' => "-...-",
+        '+' => ".-.-.",
+        '-' => "-....-",
+        '_' => "..--.-",
+        '"' => ".-..-.",
+        '$' => "...-..-",
+        '@' => ".--.-.",
+        _ => return None,
+    };
+    Some(code)
+}
+
+/// Walks the message and emits runs of on/off units.
+fn for_each_run(
+    text: &str,
+    mut emit: impl FnMut(u8, usize) -> Result<(), MorseError>,
+) -> Result<(), MorseError> {
+    let mut started = false;
+    let mut pending_gap = 0;
+    let mut prosign = false;
+    for c in text.chars() {
+        match c {
+            '<' if !prosign => {
+                prosign = true;
+                if started {
+                    pending_gap = pending_gap.max(LETTER_GAP);
+                }
+                continue;
+            }
+            '>' if prosign => {
+                prosign = false;
+                continue;
+            }
+            c if c.is_whitespace() => {
+                if prosign {
+                    return Err(MorseError::UnknownCharacter(c));
+                }
+                if started {
+                    pending_gap = WORD_GAP;
+                }
+                continue;
+            }
+            _ => {}
+        }
+        let code = code(c).ok_or(MorseError::UnknownCharacter(c))?;
+        if started {
+            // inside a prosign letters run together with only the element gap
+            let gap = if prosign && pending_gap == 0 { ELEMENT_GAP } else { pending_gap.max(LETTER_GAP) };
+            emit(0, gap)?;
+        }
+        for (i, element) in code.bytes().enumerate() {
+            if i > 0 {
+                emit(0, ELEMENT_GAP)?;
+            }
+            emit(1, if element == b'-' { 3 } else { 1 })?;
+        }
+        started = true;
+        pending_gap = 0;
+    }
+    if prosign {
+        return Err(MorseError::UnterminatedProsign);
+    }
+    Ok(())
+}
+
+/// Number of units `text` encodes to.
+pub fn encoded_len(text: &str) -> Result<usize, MorseError> {
+    let mut len = 0;
+    for_each_run(text, |_, n| {
+        len += n;
+        Ok(())
+    })?;
+    Ok(len)
+}
+
+/// Encode `text` into `out`, one byte per unit. Returns the number of units written.
+pub fn encode(text: &str, out: &mut [u8]) -> Result<usize, MorseError> {
+    let mut len = 0;
+    for_each_run(text, |bit, n| {
+        let slots = out.get_mut(len..len + n).ok_or(MorseError::BufferFull)?;
+        slots.fill(bit);
+        len += n;
+        Ok(())
+    })?;
+    Ok(len)
+}
+
+/// Encode `text` into a [heapless::Vec] of capacity `CAP`.
+#[cfg(feature = "heapless")]
+pub fn encode_vec<const CAP: usize>(text: &str) -> Result<heapless::Vec<u8, CAP>, MorseError> {
+    let mut out = heapless::Vec::new();
+    for_each_run(text, |bit, n| {
+        for _ in 0..n {
+            out.push(bit).map_err(|_| MorseError::BufferFull)?;
+        }
+        Ok(())
+    })?;
+    Ok(out)
+}
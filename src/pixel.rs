@@ -0,0 +1,126 @@
+//! Output pixel formats that effects can render into.
+//!
+//! Effects do their colour maths in `f32` (through [palette]) or directly in 8-bit where the
+//! algorithm is already integer based (e.g. [crate::strip::Fire]). The [Pixel] trait is the
+//! conversion boundary between that maths and whatever the driver wants to see in its buffer.
+
+use crate::{utils, RGB16, RGB8};
+use palette::{FromColor, Hsv, LinSrgb, Srgb};
+
+/// A pixel type that [crate::strip::RenderAs::next_line_as] can render into.
+///
+/// Implemented for [RGB8] (the default used throughout the examples), [RGB16] for 16-bit
+/// drivers, and [Srgb]/[LinSrgb] `f32` for HDR intermediates that get composited before
+/// being quantised.
+pub trait Pixel: Copy {
+    /// All channels off.
+    const BLACK: Self;
+
+    /// Convert from an encoded sRGB colour with channels in `0.0..=1.0`.
+    fn from_srgb(colour: Srgb) -> Self;
+
+    /// Convert back into an encoded sRGB colour.
+    fn to_srgb(self) -> Srgb;
+
+    /// Convert from an 8-bit colour. Integer formats should override this to avoid the
+    /// round trip through `f32`.
+    fn from_rgb8(colour: RGB8) -> Self {
+        Self::from_srgb(Srgb::new(colour.r, colour.g, colour.b).into_format())
+    }
+
+    /// Convert from an HSV colour.
+    fn from_hsv(colour: Hsv) -> Self {
+        Self::from_srgb(Srgb::from_color(colour))
+    }
+
+    /// Convert a slice of HSV colours, `min(src.len(), dst.len())` pixels.
+    /// Formats with a batched conversion override this.
+    fn from_hsv_slice(src: &[Hsv], dst: &mut [Self]) {
+        for (out, hsv) in dst.iter_mut().zip(src) {
+            *out = Self::from_hsv(*hsv);
+        }
+    }
+}
+
+impl Pixel for RGB8 {
+    const BLACK: Self = RGB8 { r: 0, g: 0, b: 0 };
+
+    fn from_srgb(colour: Srgb) -> Self {
+        let p: Srgb<u8> = colour.into_format();
+        RGB8 {
+            r: p.red,
+            g: p.green,
+            b: p.blue,
+        }
+    }
+
+    fn to_srgb(self) -> Srgb {
+        Srgb::new(self.r, self.g, self.b).into_format()
+    }
+
+    fn from_rgb8(colour: RGB8) -> Self {
+        colour
+    }
+
+    fn from_hsv(colour: Hsv) -> Self {
+        utils::hsv_to_rgb8_pixel(colour)
+    }
+
+    fn from_hsv_slice(src: &[Hsv], dst: &mut [Self]) {
+        utils::hsv_slice_to_rgb8(src, dst, None)
+    }
+}
+
+impl Pixel for RGB16 {
+    const BLACK: Self = RGB16 { r: 0, g: 0, b: 0 };
+
+    fn from_srgb(colour: Srgb) -> Self {
+        let p: Srgb<u16> = colour.into_format();
+        RGB16 {
+            r: p.red,
+            g: p.green,
+            b: p.blue,
+        }
+    }
+
+    fn to_srgb(self) -> Srgb {
+        Srgb::new(self.r, self.g, self.b).into_format()
+    }
+
+    fn from_rgb8(colour: RGB8) -> Self {
+        // 0xff * 257 == 0xffff, so full scale maps to full scale.
+        RGB16 {
+            r: colour.r as u16 * 257,
+            g: colour.g as u16 * 257,
+            b: colour.b as u16 * 257,
+        }
+    }
+}
+
+impl Pixel for Srgb {
+    const BLACK: Self = Srgb::new(0.0, 0.0, 0.0);
+
+    fn from_srgb(colour: Srgb) -> Self {
+        colour
+    }
+
+    fn to_srgb(self) -> Srgb {
+        self
+    }
+
+    fn from_hsv_slice(src: &[Hsv], dst: &mut [Self]) {
+        utils::hsv_slice_to_srgb(src, dst)
+    }
+}
+
+impl Pixel for LinSrgb {
+    const BLACK: Self = LinSrgb::new(0.0, 0.0, 0.0);
+
+    fn from_srgb(colour: Srgb) -> Self {
+        colour.into_linear()
+    }
+
+    fn to_srgb(self) -> Srgb {
+        Srgb::from_linear(self)
+    }
+}
@@ -0,0 +1,127 @@
+//! A small seedable PRNG and helpers for the random effects.
+//!
+//! Every random effect is generic over [RngCore] so any generator can be plugged in, but most
+//! embedded users just want "something random" that is reproducible from a seed. [FastRng]
+//! covers that without pulling in `rand`, and [RandomExt] wraps the float conversions the
+//! effects need.
+
+use core::ops::Range;
+use rand_core::{impls, RngCore, SeedableRng};
+
+/// xoshiro128++: 128 bits of state, only 32-bit adds, shifts and rotates per output, so it is
+/// fast on Cortex-M0 class cores without a multiplier-heavy inner loop.
+///
+/// Not cryptographically secure.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FastRng {
+    s: [u32; 4],
+}
+
+impl FastRng {
+    const DEFAULT_SEED: u64 = 0x5eed_1ed5_eed1_ed5e;
+
+    /// Create a generator from a 64-bit seed. Equal seeds give equal sequences.
+    pub const fn new(seed: u64) -> Self {
+        // expand the seed with SplitMix64 so that similar seeds give unrelated streams
+        let mut state = seed;
+        let a = splitmix64(&mut state);
+        let b = splitmix64(&mut state);
+        let mut s = [a as u32, (a >> 32) as u32, b as u32, (b >> 32) as u32];
+        if s[0] | s[1] | s[2] | s[3] == 0 {
+            // the all-zero state is a fixed point
+            s[0] = 1;
+        }
+        Self { s }
+    }
+}
+
+impl Default for FastRng {
+    fn default() -> Self {
+        Self::new(Self::DEFAULT_SEED)
+    }
+}
+
+const fn splitmix64(state: &mut u64) -> u64 {
+    *state = state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+    let mut z = *state;
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    z ^ (z >> 31)
+}
+
+/// Hash a seed and a slot number (e.g. a time slot or a pixel index) into a well-mixed `u32`.
+///
+/// Effects that must produce the same "random" output on several devices derive their
+/// randomness from this instead of from a running generator.
+pub const fn hash32(seed: u64, slot: u64) -> u32 {
+    let mut state = seed ^ slot.wrapping_mul(0xd1b5_4a32_d192_ed03);
+    (splitmix64(&mut state) >> 32) as u32
+}
+
+impl RngCore for FastRng {
+    fn next_u32(&mut self) -> u32 {
+        let s = &mut self.s;
+        let result = s[0].wrapping_add(s[3]).rotate_left(7).wrapping_add(s[0]);
+        let t = s[1] << 9;
+        s[2] ^= s[0];
+        s[3] ^= s[1];
+        s[1] ^= s[2];
+        s[0] ^= s[3];
+        s[2] ^= t;
+        s[3] = s[3].rotate_left(11);
+        result
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        impls::next_u64_via_u32(self)
+    }
+
+    fn fill_bytes(&mut self, dst: &mut [u8]) {
+        impls::fill_bytes_via_next(self, dst)
+    }
+}
+
+impl SeedableRng for FastRng {
+    type Seed = [u8; 16];
+
+    fn from_seed(seed: Self::Seed) -> Self {
+        let mut s = [0u32; 4];
+        for (word, bytes) in s.iter_mut().zip(seed.chunks_exact(4)) {
+            *word = u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]);
+        }
+        if s == [0; 4] {
+            return Self::new(0);
+        }
+        Self { s }
+    }
+
+    fn seed_from_u64(state: u64) -> Self {
+        Self::new(state)
+    }
+}
+
+/// Convenience conversions on top of any [RngCore].
+pub trait RandomExt: RngCore {
+    /// Uniform float in `0.0..1.0`.
+    fn unit_f32(&mut self) -> f32 {
+        // 24 bits is all an f32 mantissa holds, and keeps the result strictly below 1.0
+        (self.next_u32() >> 8) as f32 * (1.0 / (1u32 << 24) as f32)
+    }
+
+    /// Uniform float in `range`.
+    fn range_f32(&mut self, range: Range<f32>) -> f32 {
+        range.start + self.unit_f32() * (range.end - range.start)
+    }
+
+    /// Returns true with the given probability (`0.0..=1.0`).
+    fn chance(&mut self, probability: f32) -> bool {
+        self.unit_f32() < probability
+    }
+
+    /// Index in `0..len`. `len` must be non-zero.
+    fn index(&mut self, len: usize) -> usize {
+        (self.next_u32() as usize) % len
+    }
+}
+
+impl<R: RngCore + ?Sized> RandomExt for R {}
@@ -0,0 +1,158 @@
+use crate::{strip::{EffectIterator, RenderAs}, Pixel, RGB8};
+use palette::{FromColor, Hsv, Srgb};
+
+/// Threshold based colouring: the part of a bar below `warning` is `normal`, up to `danger`
+/// is `warning_colour` and above is `danger_colour`. Thresholds are fractions of the bar.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Zones {
+    pub warning: f32,
+    pub danger: f32,
+    pub normal: Srgb,
+    pub warning_colour: Srgb,
+    pub danger_colour: Srgb,
+}
+
+impl Zones {
+    pub fn colour(&self, fraction: f32) -> Srgb {
+        if fraction >= self.danger {
+            self.danger_colour
+        } else if fraction >= self.warning {
+            self.warning_colour
+        } else {
+            self.normal
+        }
+    }
+}
+
+/// Green to 60%, yellow to 85%, red above.
+impl Default for Zones {
+    fn default() -> Self {
+        Self {
+            warning: 0.6,
+            danger: 0.85,
+            normal: Srgb::new(0.0, 1.0, 0.0),
+            warning_colour: Srgb::new(1.0, 0.8, 0.0),
+            danger_colour: Srgb::new(1.0, 0.0, 0.0),
+        }
+    }
+}
+
+/// Fill `segment` to `value` (0 to 1) with the leading pixel lit proportionally.
+/// `colour` gets the position of each pixel as a fraction of the segment.
+pub(super) fn render_bar<P: Pixel>(
+    segment: &mut [P],
+    value: f32,
+    reverse: bool,
+    colour: impl Fn(f32) -> Srgb,
+) {
+    let extent = segment.len() as f32;
+    let count = segment.len();
+    for (i, slot) in segment.iter_mut().enumerate() {
+        let position = if reverse { count - 1 - i } else { i } as f32;
+        let lit = (value * extent - position).clamp(0.0, 1.0);
+        *slot = if lit > 0.0 {
+            P::from_srgb(colour(position / extent) * lit)
+        } else {
+            P::BLACK
+        };
+    }
+}
+
+/// Splits the strip into `K` equal bars, each showing its own percentage.
+///
+/// Handy for per-core CPU load or several downloads at once. Bars are coloured individually,
+/// or by [Zones] when set.
+pub struct BarGraph<const N: usize, const K: usize> {
+    values: [f32; K],
+    colours: [Srgb; K],
+    zones: Option<Zones>,
+    gap: usize,
+}
+
+impl<const N: usize, const K: usize> BarGraph<N, K> {
+    const DEFAULT_GAP: usize = 1;
+
+    /// `colours` defaults to hues spread evenly around the colour wheel, `gap` is the number
+    /// of dark pixels between bars (1 by default).
+    pub fn new(colours: Option<[Srgb; K]>, gap: Option<usize>) -> Self {
+        Self {
+            values: [0.0; K],
+            colours: colours.unwrap_or_else(|| {
+                core::array::from_fn(|i| {
+                    Srgb::from_color(Hsv::new(i as f32 * 360.0 / K as f32, 1.0, 1.0))
+                })
+            }),
+            zones: None,
+            gap: gap.unwrap_or(Self::DEFAULT_GAP),
+        }
+    }
+
+    /// Bars with green/yellow/red [Zones].
+    pub fn with_zones(zones: Option<Zones>, gap: Option<usize>) -> Self {
+        let mut me = Self::new(None, gap);
+        me.zones = Some(zones.unwrap_or_default());
+        me
+    }
+
+    /// Set bar `index` to `percentage` (0 to 100). Out of range indices are ignored.
+    pub fn set_percentage(&mut self, index: usize, percentage: f32) {
+        if let Some(value) = self.values.get_mut(index) {
+            *value = percentage.clamp(0.0, 100.0);
+        }
+    }
+
+    /// Set the bars from a slice of percentages, starting at bar 0.
+    pub fn set_percentages(&mut self, percentages: &[f32]) {
+        for (i, p) in percentages.iter().enumerate() {
+            self.set_percentage(i, *p);
+        }
+    }
+
+    pub fn set_colour(&mut self, index: usize, colour: Srgb) {
+        if let Some(c) = self.colours.get_mut(index) {
+            *c = colour;
+        }
+    }
+
+    pub fn set_zones(&mut self, zones: Option<Zones>) {
+        self.zones = zones;
+    }
+}
+
+impl<const N: usize, const K: usize> EffectIterator for BarGraph<N, K> {
+    fn name(&self) -> &'static str {
+        "BarGraph"
+    }
+
+    fn next_line(&mut self, buf: &mut [RGB8], dt_ticks: u32) -> Option<usize> {
+        self.next_line_as(buf, dt_ticks)
+    }
+
+    fn pixel_count(&self) -> usize {
+        N
+    }
+}
+
+impl<const N: usize, const K: usize> RenderAs for BarGraph<N, K> {
+    fn next_line_as<P: Pixel>(&mut self, buf: &mut [P], _dt_ticks: u32) -> Option<usize> {
+        let len = core::cmp::min(N, buf.len());
+        for slot in buf.iter_mut().take(len) { *slot = P::BLACK; }
+        if K == 0 {
+            return Some(len);
+        }
+        let width = N.saturating_sub(self.gap * (K - 1)) / K;
+        for bar in 0..K {
+            let start = bar * (width + self.gap);
+            let end = core::cmp::min(start + width, len);
+            if start >= end {
+                break;
+            }
+            let base = self.colours[bar];
+            let zones = self.zones;
+            render_bar(&mut buf[start..end], self.values[bar] / 100.0, false, |f| {
+                zones.map_or(base, |z| z.colour(f))
+            });
+        }
+        Some(len)
+    }
+}
@@ -1,20 +1,21 @@
-use crate::{strip::EffectIterator, RGB8};
+use crate::{
+    rng::{FastRng, RandomExt},
+    strip::{
+        draw::{blend, span},
+        EffectIterator, RenderAs,
+    },
+    Pixel, RGB8,
+};
 use core::ops::Range;
 use palette::{Darken, FromColor, Hsv, Srgb};
 use rand_core::RngCore;
 
-#[derive(Debug, Clone, Copy)]
-enum Direction {
-    Up,
-    Down,
-}
-
 #[derive(Debug, Clone)]
 struct Ball {
-    position: f32,
-    speed: f32, /* pixels per a second */
+    position: f32, /* centre, in pixels from index 0 */
+    velocity: f32, /* pixels per a second, positive away from index 0 */
+    size: f32,
     colour: Srgb,
-    direction: Direction,
     random_colour: bool,
     gravity: f32,
     bounciness: Range<f32>,
@@ -26,6 +27,10 @@ impl Ball {
     const DEFAULT_GRAVITY: f32 = 30.0; // pixels per a second ^ 2
     const DEFAULT_BOUNCINESS: Range<f32> = 0.2..0.8;
     const DEFAULT_SPEEDS: Range<f32> = 20.0..80.0;
+    const DEFAULT_SIZE: f32 = 1.0;
+    /// Rebounds slower than this count as having stopped bouncing.
+    const MIN_SPEED: f32 = 1.0;
+
     fn new_params(
         colour: Option<Srgb>,
         gravity: Option<f32>,
@@ -35,10 +40,10 @@ impl Ball {
         let is_random = colour.is_none();
         let colour = colour.unwrap_or(Srgb::from_color(Hsv::new(0.0, 1.0, 1.0)));
         Self {
-            position: 0.0,
-            speed: 0.0,
+            position: Self::DEFAULT_SIZE / 2.0,
+            velocity: 0.0,
+            size: Self::DEFAULT_SIZE,
             colour,
-            direction: Direction::Up,
             random_colour: is_random,
             gravity: gravity.unwrap_or(Self::DEFAULT_GRAVITY),
             bounciness: bounciness.unwrap_or(Self::DEFAULT_BOUNCINESS),
@@ -47,51 +52,67 @@ impl Ball {
         }
     }
 
+    fn radius(&self) -> f32 {
+        self.size / 2.0
+    }
+
+    /// Mass for collisions, proportional to size.
+    fn mass(&self) -> f32 {
+        self.size.max(0.01)
+    }
+
     fn reset<R: RngCore>(&mut self, rng: &mut R) {
-        let sp_lo = self.speed_range.start;
-        let sp_hi = self.speed_range.end;
-        let r = (rng.next_u32() as f32) / (u32::MAX as f32 + 1.0);
-        self.speed = sp_lo + r * (sp_hi - sp_lo);
+        self.velocity = rng.range_f32(self.speed_range.clone());
         if self.random_colour {
-            let hue = (rng.next_u32() as f32) / (u32::MAX as f32 + 1.0) * 360.0;
+            let hue = rng.range_f32(0.0..360.0);
             self.colour = Srgb::from_color(Hsv::new(hue, 1.0, 1.0));
         }
-        let b_lo = self.bounciness.start;
-        let b_hi = self.bounciness.end;
-        let r2 = (rng.next_u32() as f32) / (u32::MAX as f32 + 1.0);
-        self.current_bounciness = b_lo + r2 * (b_hi - b_lo);
-    }
-
-    fn update<R: RngCore>(&mut self, dt_sec: f32, rng: &mut R) {
-        let elapsed = dt_sec;
-        match self.direction {
-            Direction::Up => {
-                let d1 = self.speed * elapsed / 2.0;
-                self.speed -= self.gravity * elapsed;
-                let d2 = self.speed * elapsed / 2.0;
-                self.position += (d1 + d2).max(0.0);
-                if self.speed <= 1.0 {
-                    if self.position < 0.5 {
-                        self.reset(rng);
-                    } else {
-                        self.direction = Direction::Down;
-                    }
-                }
+        self.current_bounciness = rng.range_f32(self.bounciness.clone());
+    }
+
+    fn update<R: RngCore>(&mut self, dt_sec: f32, ceiling: Option<f32>, perpetual: bool, rng: &mut R) {
+        let v1 = self.velocity;
+        self.velocity -= self.gravity * dt_sec;
+        self.position += (v1 + self.velocity) * dt_sec / 2.0;
+
+        if let Some(top) = ceiling {
+            if self.position + self.radius() >= top && self.velocity > 0.0 {
+                self.position = top - self.radius();
+                self.velocity = -self.velocity * self.current_bounciness;
             }
-            Direction::Down => {
-                let d1 = self.speed * elapsed / 2.0;
-                self.speed += self.gravity * elapsed;
-                let d2 = self.speed * elapsed / 2.0;
-                self.position -= (d1 + d2).max(0.0);
-                if self.position <= 0.0 {
-                    self.direction = Direction::Up;
-                    self.speed *= self.current_bounciness;
+        }
+        if self.position - self.radius() <= 0.0 && self.velocity <= 0.0 {
+            self.position = self.radius();
+            self.velocity = -self.velocity * self.current_bounciness;
+            if perpetual {
+                // top up the energy lost in the bounce with a fresh launch
+                if self.velocity < self.speed_range.start {
+                    self.velocity = rng.range_f32(self.speed_range.clone());
                 }
+            } else if self.velocity < Self::MIN_SPEED {
+                self.reset(rng);
             }
         }
     }
-    fn location(&self) -> usize {
-        self.position as usize
+
+    /// Elastic collision in one dimension, if the two balls overlap and are approaching.
+    fn collide(a: &mut Ball, b: &mut Ball) {
+        let distance = b.position - a.position;
+        let overlap = a.radius() + b.radius() - distance.abs();
+        if overlap <= 0.0 {
+            return;
+        }
+        let (ma, mb) = (a.mass(), b.mass());
+        // push apart in proportion to the other's mass, `b` upwards when level
+        let sign = if distance < 0.0 { -1.0 } else { 1.0 };
+        a.position -= sign * overlap * mb / (ma + mb);
+        b.position += sign * overlap * ma / (ma + mb);
+
+        if (b.velocity - a.velocity) * sign < 0.0 {
+            let (va, vb) = (a.velocity, b.velocity);
+            a.velocity = ((ma - mb) * va + 2.0 * mb * vb) / (ma + mb);
+            b.velocity = ((mb - ma) * vb + 2.0 * ma * va) / (ma + mb);
+        }
     }
 }
 
@@ -106,26 +127,38 @@ impl Ball {
 /// When created with default parameters, the effect will generate 3 balls with random colours, speeds and bounciness.
 /// When a ball stops bouncing, it will be reset with new random parameters.
 ///
+/// Balls are rendered with sub-pixel precision and can be given sizes with [Bounce::set_size].
+/// [Bounce::set_collisions] makes them bounce off each other (heavier balls being the larger
+/// ones), [Bounce::set_ceiling] bounces them off the far end as well, and
+/// [Bounce::set_perpetual] keeps them bouncing rather than relaunching them.
+///
 /// # Example
 ///
 /// Basic usage:
 ///
 /// ```rust
-/// let mut effect = strip::Bounce::new(55, None, None, None, None, None);
-/// let pixels = effect.next().unwrap();
+/// use smart_led_effects::{strip::{self, EffectIterator}, RGB8};
+///
+/// let mut effect = strip::Bounce::<55, 3>::seeded(1, None, None, None, None);
+/// let mut pixels = [RGB8::default(); 55];
+/// effect.next_line(&mut pixels, 16);
 /// ```
 ///
 /// # Arguments
 ///
-/// - `count` - The number of LEDs in the strip.
+/// - `N` - The number of LEDs in the strip.
+/// - `M` - The number of balls to generate.
+/// - `rng` - The random number source.
 /// - `colour` - The colour of the balls. If None, the colour will be randomised for each ball.
-/// - `balls` - The number of balls to generate. If None, 3 balls will be generated.
 /// - `gravity` - The gravity of the balls. If None, the default value will be used.
 /// - `bounciness` - The bounciness of the balls. If None, the default range will be used.
 /// - `speed` - The speed range of the balls. If None, the default range will be used.
-pub struct Bounce<const N: usize, const M: usize, R: RngCore> {
+pub struct Bounce<const N: usize, const M: usize, R: RngCore = FastRng> {
     balls: [Ball; M],
     rng: R,
+    collisions: bool,
+    ceiling: bool,
+    perpetual: bool,
 }
 
 impl<const N: usize, const M: usize, R: RngCore> Bounce<N, M, R> {
@@ -142,7 +175,71 @@ impl<const N: usize, const M: usize, R: RngCore> Bounce<N, M, R> {
         for b in balls.iter_mut() {
             b.reset(&mut rng);
         }
-        Self { balls, rng }
+        Self {
+            balls,
+            rng,
+            collisions: false,
+            ceiling: false,
+            perpetual: false,
+        }
+    }
+
+    /// Let the balls bounce off each other. Off by default, when they pass through each other.
+    pub fn set_collisions(&mut self, collisions: bool) {
+        self.collisions = collisions;
+    }
+
+    /// Bounce off the far end of the strip too, instead of flying past it.
+    pub fn set_ceiling(&mut self, ceiling: bool) {
+        self.ceiling = ceiling;
+    }
+
+    /// Keep the balls bouncing by topping up their energy instead of relaunching them once
+    /// they stop.
+    pub fn set_perpetual(&mut self, perpetual: bool) {
+        self.perpetual = perpetual;
+    }
+
+    /// Size of ball `index` in pixels (1.0 by default). Also its mass in collisions.
+    pub fn set_size(&mut self, index: usize, size: f32) {
+        if let Some(ball) = self.balls.get_mut(index) {
+            ball.size = size.max(0.0);
+            ball.position = ball.position.max(ball.radius());
+        }
+    }
+
+    fn physics(&mut self, dt_sec: f32) {
+        let ceiling = self.ceiling.then_some(N as f32);
+        for ball in self.balls.iter_mut() {
+            ball.update(dt_sec, ceiling, self.perpetual, &mut self.rng);
+        }
+        if self.collisions {
+            for i in 0..M {
+                let (head, tail) = self.balls.split_at_mut(i + 1);
+                for other in tail.iter_mut() {
+                    Ball::collide(&mut head[i], other);
+                }
+            }
+            // collisions may have pushed balls through the ends
+            for ball in self.balls.iter_mut() {
+                let low = ball.radius();
+                let high = ceiling.map_or(f32::MAX, |top| top - ball.radius());
+                ball.position = ball.position.clamp(low, high.max(low));
+            }
+        }
+    }
+}
+
+impl<const N: usize, const M: usize> Bounce<N, M> {
+    /// Same as [Bounce::new] using a [FastRng] seeded with `seed`.
+    pub fn seeded(
+        seed: u64,
+        colour: Option<Srgb>,
+        gravity: Option<f32>,
+        bounciness: Option<Range<f32>>,
+        speed: Option<Range<f32>>,
+    ) -> Self {
+        Self::new(FastRng::new(seed), colour, gravity, bounciness, speed)
     }
 }
 
@@ -151,45 +248,37 @@ impl<const N: usize, const M: usize, R: RngCore> EffectIterator for Bounce<N, M,
         "Bounce"
     }
     fn next_line(&mut self, buf: &mut [RGB8], dt_ticks: u32) -> Option<usize> {
+        self.next_line_as(buf, dt_ticks)
+    }
+
+    fn pixel_count(&self) -> usize {
+        N
+    }
+}
+
+impl<const N: usize, const M: usize, R: RngCore> RenderAs for Bounce<N, M, R> {
+    fn next_line_as<P: Pixel>(&mut self, buf: &mut [P], dt_ticks: u32) -> Option<usize> {
         let len = core::cmp::min(N, buf.len());
-        for slot in buf.iter_mut().take(len) { *slot = RGB8 { r:0, g:0, b:0 }; }
+        for slot in buf.iter_mut().take(len) { *slot = P::BLACK; }
         let dt_sec = dt_ticks as f32 / 1000.0;
-        for ball in self.balls.iter_mut() {
-            ball.update(dt_sec, &mut self.rng);
-            let pixel = ball.location();
-            let mut tail_len = ((ball.speed * 0.5) as usize).saturating_add(1);
-            if tail_len > pixel {
-                tail_len = pixel;
-            }
-            for i in 0..(tail_len as i32) {
-                let pos: i32 = match ball.direction {
-                    Direction::Up => ball.location() as i32 - i,
-                    Direction::Down => ball.location() as i32 + i,
-                };
-                if pos >= 0 && (pos as usize) < len {
-                    let mut colour: Srgb = ball.colour.into_format();
-                    colour = colour.darken_fixed(i as f32 / tail_len as f32);
-                    let p = colour.into_format::<u8>();
-                    buf[pos as usize] = RGB8 {
-                        r: p.red,
-                        g: p.green,
-                        b: p.blue,
-                    };
-                }
-            }
-            if pixel < len {
-                let p = ball.colour.into_format::<u8>();
-                buf[pixel] = RGB8 {
-                    r: p.red,
-                    g: p.green,
-                    b: p.blue,
-                };
+        self.physics(dt_sec);
+        let buf = &mut buf[..len];
+        for ball in self.balls.iter() {
+            let low = ball.position - ball.radius();
+            let high = ball.position + ball.radius();
+            let head = ball.position as i32;
+
+            // fading trail behind the ball, one pixel per 2 px/s
+            let speed = ball.velocity.abs();
+            let tail_len = ((speed * 0.5) as i32).saturating_add(1).min(head);
+            let behind = if ball.velocity > 0.0 { -1 } else { 1 };
+            for i in 1..tail_len {
+                let colour = ball.colour.darken_fixed(i as f32 / tail_len as f32);
+                blend(buf, head + behind * i, colour, 1.0);
             }
+
+            span(buf, low, high, ball.colour);
         }
         Some(len)
     }
-
-    fn pixel_count(&self) -> usize {
-        N
-    }
 }
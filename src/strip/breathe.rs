@@ -1,31 +1,335 @@
-use crate::{strip::EffectIterator, RGB8};
-use palette::Hsv;
+use crate::{
+    math8::ease16_in_out_sine,
+    rng::FastRng,
+    strip::{EffectIterator, PixelStream, RenderAs, Seek},
+    Pixel, RGB8,
+};
+use core::ops::Range;
+use palette::{FromColor, Hsv, Srgb};
 use rand_core::RngCore;
 
+/// Shape of the brightness curve over a breath.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum BreatheWave {
+    /// Linear rise and fall.
+    #[default]
+    Triangle,
+    /// Sinusoidal, lingering at the top and bottom.
+    Sine,
+    /// `e^sin(t)`, like the sleep indicator on old Apple laptops: a long dim pause and a
+    /// short bright breath.
+    Exponential,
+    /// Brightness samples for the rise, from the trough to the peak, interpolated between
+    /// them. The fall mirrors the rise.
+    Custom(&'static [u8]),
+}
+
+impl BreatheWave {
+    /// `(e^(2x-1) - 1/e) / (e - 1/e)` in 16ths of the sine eased ramp.
+    const EXPONENTIAL: [u16; 17] = [
+        0, 1366, 2913, 4667, 6654, 8906, 11457, 14349, 17625, 21338, 25544, 30311, 35713, 41834,
+        48770, 56629, 65535,
+    ];
+
+    /// Brightness for a position `level` along the rise.
+    fn shape(&self, level: u16) -> u16 {
+        match self {
+            BreatheWave::Triangle => level,
+            BreatheWave::Sine => ease16_in_out_sine(level),
+            BreatheWave::Exponential => {
+                let eased = ease16_in_out_sine(level);
+                interpolate(&Self::EXPONENTIAL, eased, |v| v)
+            }
+            BreatheWave::Custom(table) => interpolate(table, level, |v| v as u16 * 257),
+        }
+    }
+}
+
+/// Linear interpolation through evenly spaced samples, `position` spanning the whole table.
+fn interpolate<T: Copy>(table: &[T], position: u16, to_u16: impl Fn(T) -> u16) -> u16 {
+    match table.len() {
+        0 => position,
+        1 => to_u16(table[0]),
+        len => {
+            let scaled = position as u32 * (len as u32 - 1);
+            let index = (scaled / u16::MAX as u32) as usize;
+            let frac = (scaled % u16::MAX as u32) as i64;
+            let a = to_u16(table[index]) as i64;
+            let b = to_u16(table[(index + 1).min(len - 1)]) as i64;
+            (a + (b - a) * frac / u16::MAX as i64) as u16
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq)]
 enum Direction {
     Up,
+    Peak,
     Down,
+    Trough,
+}
+
+/// Triangle ramp of the brightness, kept as a 16-bit fraction so that the
+/// `int-math` path needs no floats.
+struct Ramp {
+    direction: Direction,
+    level: u16,
+    step: u16,
+    period_ticks: Option<u32>,
+    free_running: bool,
+    wave: BreatheWave,
+    low: u16,
+    high: u16,
+    hold_peak: u32,
+    hold_trough: u32,
+    held: u32,
+    /// Ticks into the current breath, when there is a period.
+    clock: u64,
+}
+
+impl Ramp {
+    fn new(step: f32) -> Self {
+        Self {
+            direction: Direction::Up,
+            level: 0,
+            step: ((step * u16::MAX as f32) as u16).max(1),
+            period_ticks: None,
+            free_running: true,
+            wave: BreatheWave::default(),
+            low: 0,
+            high: u16::MAX,
+            hold_peak: 0,
+            hold_trough: 0,
+            held: 0,
+            clock: 0,
+        }
+    }
+
+    fn set_range(&mut self, min: f32, max: f32) {
+        let to_u16 = |v: f32| (v.clamp(0.0, 1.0) * u16::MAX as f32) as u16;
+        self.low = to_u16(min);
+        self.high = to_u16(max).max(self.low);
+    }
+
+    /// Output brightness as a 16-bit fraction.
+    fn brightness(&self) -> u16 {
+        let shaped = self.wave.shape(self.level) as u32;
+        self.low + ((self.high - self.low) as u32 * shaped / u16::MAX as u32) as u16
+    }
+
+    /// Jump up to `level` if it is brighter and fade from there.
+    fn kick(&mut self, level: f32) {
+        let level = (level.clamp(0.0, 1.0) * u16::MAX as f32) as u16;
+        if level > self.level {
+            self.level = level;
+            self.direction = Direction::Down;
+            self.sync_clock();
+        }
+    }
+
+    fn set_period(&mut self, period_ticks: Option<u32>) {
+        self.period_ticks = period_ticks.map(|p| p.max(1));
+        self.sync_clock();
+    }
+
+    fn set_hold(&mut self, peak_ticks: u32, trough_ticks: u32) {
+        self.hold_peak = peak_ticks;
+        self.hold_trough = trough_ticks;
+        self.sync_clock();
+    }
+
+    /// Set the clock from the direction and level, so changing the period or the holds
+    /// carries on from the same point of the breath.
+    fn sync_clock(&mut self) {
+        let Some((rise, peak, fall, trough)) = self.segments() else {
+            return;
+        };
+        let max = u16::MAX as u64;
+        let level = self.level as u64;
+        self.clock = match self.direction {
+            Direction::Up => level * rise / max,
+            Direction::Peak => rise + (self.held as u64).min(peak.saturating_sub(1)),
+            Direction::Down => rise + peak + (max - level) * fall / max,
+            Direction::Trough => rise + peak + fall + (self.held as u64).min(trough.saturating_sub(1)),
+        };
+        self.place();
+    }
+
+    /// Lengths of the rise, peak hold, fall and trough hold in ticks, when there is a period.
+    fn segments(&self) -> Option<(u64, u64, u64, u64)> {
+        let period = self.period_ticks? as u64;
+        let rise = period / 2;
+        Some((rise, self.hold_peak as u64, period - rise, self.hold_trough as u64))
+    }
+
+    /// Set the direction and level from the clock.
+    fn place(&mut self) {
+        let Some((rise, peak, fall, _)) = self.segments() else {
+            return;
+        };
+        let max = u16::MAX as u64;
+        let c = self.clock;
+        (self.direction, self.level) = if c < rise {
+            (Direction::Up, (c * max / rise.max(1)) as u16)
+        } else if c < rise + peak {
+            self.held = (c - rise) as u32;
+            (Direction::Peak, u16::MAX)
+        } else if c < rise + peak + fall {
+            (Direction::Down, (max - (c - rise - peak) * max / fall) as u16)
+        } else {
+            self.held = (c - rise - peak - fall) as u32;
+            (Direction::Trough, 0)
+        };
+    }
+
+    /// Returns true when the ramp has returned to zero and a new cycle starts.
+    fn advance(&mut self, dt_ticks: u32) -> bool {
+        if let Some((rise, peak, fall, trough)) = self.segments() {
+            return self.advance_clock(dt_ticks, rise + peak + fall, rise + peak + fall + trough);
+        }
+        let step = self.step;
+        match self.direction {
+            Direction::Up if !self.free_running => false,
+            Direction::Up => {
+                self.level = self.level.saturating_add(step);
+                if self.level == u16::MAX {
+                    self.hold(Direction::Peak);
+                }
+                false
+            }
+            Direction::Down => {
+                self.level = self.level.saturating_sub(step);
+                if self.level == 0 {
+                    self.hold(Direction::Trough);
+                    true
+                } else {
+                    false
+                }
+            }
+            Direction::Peak | Direction::Trough => {
+                self.held = self.held.saturating_add(dt_ticks);
+                self.release();
+                false
+            }
+        }
+    }
+
+    /// [Ramp::advance] with a period: move the clock on, `bottom` being where the fall ends.
+    fn advance_clock(&mut self, dt_ticks: u32, bottom: u64, cycle: u64) -> bool {
+        if self.direction == Direction::Up && !self.free_running {
+            return false;
+        }
+        let before = self.clock;
+        self.clock += dt_ticks as u64;
+        let new_cycle = (before < bottom && self.clock >= bottom) || self.clock >= cycle + bottom;
+        if self.clock >= cycle {
+            // without free running the breath waits at the start of the rise
+            self.clock = if self.free_running { self.clock % cycle } else { 0 };
+        }
+        self.place();
+        new_cycle
+    }
+
+    /// Stop at the peak or trough, moving on straight away if there is no hold time.
+    fn hold(&mut self, at: Direction) {
+        self.direction = at;
+        self.held = 0;
+        self.release();
+    }
+
+    fn release(&mut self) {
+        match self.direction {
+            Direction::Peak if self.held >= self.hold_peak => self.direction = Direction::Down,
+            Direction::Trough if self.held >= self.hold_trough => self.direction = Direction::Up,
+            _ => {}
+        }
+    }
+
+    /// Jump to the state after `step` calls to [Ramp::advance] of `step_ticks` each.
+    /// Returns the number of completed cycles.
+    fn seek_ticks(&mut self, step: u64, step_ticks: u32) -> u64 {
+        let Some((rise, peak, fall, trough)) = self.segments() else {
+            return self.seek(step);
+        };
+        let (bottom, cycle) = (rise + peak + fall, rise + peak + fall + trough);
+        let ticks = step * step_ticks as u64;
+        self.clock = ticks % cycle;
+        self.place();
+        if ticks < bottom {
+            0
+        } else {
+            (ticks - bottom) / cycle + 1
+        }
+    }
+
+    /// Move to `phase` (0 to 1) of the way through a breath, starting at the trough.
+    fn set_phase(&mut self, phase: f32) {
+        let phase = phase.clamp(0.0, 1.0);
+        match self.segments() {
+            Some((rise, peak, fall, trough)) => {
+                let cycle = rise + peak + fall + trough;
+                self.clock = ((phase * cycle as f32) as u64).min(cycle - 1);
+                self.place();
+            }
+            None => {
+                let cycle = 2 * u16::MAX.div_ceil(self.step) as u64;
+                self.seek((phase * cycle as f32) as u64 % cycle);
+            }
+        }
+    }
+
+    /// Jump to the state after `step` calls to [Ramp::advance] without a period.
+    /// Returns the number of completed cycles.
+    fn seek(&mut self, step: u64) -> u64 {
+        let half = u16::MAX.div_ceil(self.step) as u64;
+        let q = step % (2 * half);
+        if q < half {
+            self.level = (q * self.step as u64).min(u16::MAX as u64) as u16;
+            self.direction = Direction::Up;
+        } else {
+            self.level = u16::MAX.saturating_sub(((q - half) as u16).saturating_mul(self.step));
+            self.direction = Direction::Down;
+        }
+        step / (2 * half)
+    }
+
+    #[cfg(not(feature = "int-math"))]
+    fn shade<P: Pixel>(&self, colour: Hsv) -> P {
+        P::from_hsv(Hsv::new(
+            colour.hue,
+            colour.saturation,
+            self.brightness() as f32 / u16::MAX as f32,
+        ))
+    }
+
+    #[cfg(feature = "int-math")]
+    fn shade<P: Pixel>(&self, peak: RGB8) -> P {
+        P::from_rgb8(crate::math8::scale8_rgb(peak, (self.brightness() >> 8) as u8))
+    }
 }
 
 /// Non-random Breathe (fixed hue)
 pub struct Breathe<const N: usize> {
+    #[cfg(not(feature = "int-math"))]
     colour: Hsv,
-    direction: Direction,
-    step: f32,
+    #[cfg(feature = "int-math")]
+    peak: RGB8,
+    ramp: Ramp,
 }
 
 impl<const N: usize> Breathe<N> {
     const DEFAULT_STEP: f32 = 0.02;
     pub fn new_fixed(colour: Option<RGB8>, step_size: Option<f32>) -> Self {
-        let mut colour = match colour {
+        let colour = match colour {
             Some(rgb) => crate::utils::rgb8_to_hsv(rgb),
             None => Hsv::new(0.0, 1.0, 1.0),
         };
-        colour.value = 0.0;
         Self {
+            #[cfg(not(feature = "int-math"))]
             colour,
-            direction: Direction::Up,
-            step: step_size.unwrap_or(Self::DEFAULT_STEP),
+            #[cfg(feature = "int-math")]
+            peak: crate::utils::hsv_to_rgb8_pixel(Hsv::new(colour.hue, colour.saturation, 1.0)),
+            ramp: Ramp::new(step_size.unwrap_or(Self::DEFAULT_STEP)),
         }
     }
 
@@ -33,6 +337,74 @@ impl<const N: usize> Breathe<N> {
         let white = crate::RGB8 { r: 255, g: 255, b: 255 };
         Self::new_fixed(Some(white), step_size)
     }
+
+    /// Length of one breath in ticks, e.g. [crate::beat::BeatClock::bar_ticks], instead of a
+    /// fixed step per frame. Hold times come on top. This only follows the tempo; use
+    /// [Breathe::set_phase] to line the breath up with the beat. Without a period, [Seek]
+    /// skips the holds.
+    pub fn set_period(&mut self, period_ticks: Option<u32>) {
+        self.ramp.set_period(period_ticks);
+    }
+
+    /// Jump to `phase` (0 to 1) of the way through a breath, 0 being the start of the rise,
+    /// e.g. `breathe.set_phase(clock.bar_phase(now))` on each downbeat of a
+    /// [crate::beat::BeatClock].
+    pub fn set_phase(&mut self, phase: f32) {
+        self.ramp.set_phase(phase);
+    }
+
+    /// Brightness curve, [BreatheWave::Triangle] by default.
+    pub fn set_wave(&mut self, wave: BreatheWave) {
+        self.ramp.wave = wave;
+    }
+
+    /// Brightness at the trough and at the peak, 0 and 1 by default.
+    pub fn set_range(&mut self, min: f32, max: f32) {
+        self.ramp.set_range(min, max);
+    }
+
+    /// Ticks to stay at the peak and at the trough of each breath, 0 by default.
+    pub fn set_hold(&mut self, peak_ticks: u32, trough_ticks: u32) {
+        self.ramp.set_hold(peak_ticks, trough_ticks);
+    }
+
+    /// Brighten to at least `level` (0 to 1) and fade from there, e.g. on each bass hit
+    /// from [crate::audio::Analyser::bass].
+    pub fn pulse(&mut self, level: f32) {
+        self.ramp.kick(level);
+    }
+
+    /// When false the breath no longer rises on its own and only lights up on
+    /// [Breathe::pulse]. True by default.
+    pub fn set_free_running(&mut self, free_running: bool) {
+        self.ramp.free_running = free_running;
+    }
+}
+
+impl<const N: usize> PixelStream for Breathe<N> {
+    fn advance(&mut self, dt: u32) {
+        self.ramp.advance(dt);
+    }
+
+    fn pixel<P: Pixel>(&self, _index: usize) -> P {
+        #[cfg(not(feature = "int-math"))]
+        return self.ramp.shade(self.colour);
+        #[cfg(feature = "int-math")]
+        return self.ramp.shade(self.peak);
+    }
+
+    fn render<P: Pixel>(&self, buf: &mut [P]) -> usize {
+        let px = self.pixel(0);
+        let len = core::cmp::min(N, buf.len());
+        for slot in buf.iter_mut().take(len) { *slot = px; }
+        len
+    }
+}
+
+impl<const N: usize> Seek for Breathe<N> {
+    fn seek(&mut self, step: u64, step_ticks: u32, _seed: u64) {
+        self.ramp.seek_ticks(step, step_ticks);
+    }
 }
 
 impl<const N: usize> EffectIterator for Breathe<N> {
@@ -40,26 +412,8 @@ impl<const N: usize> EffectIterator for Breathe<N> {
         "Breathe"
     }
 
-    fn next_line(&mut self, buf: &mut [RGB8], _dt: u32) -> Option<usize> {
-        match self.direction {
-            Direction::Up => {
-                self.colour.value += self.step;
-                if self.colour.value >= 1.0 {
-                    self.direction = Direction::Down;
-                }
-            }
-            Direction::Down => {
-                self.colour.value -= self.step;
-                if self.colour.value <= 0.0 {
-                    self.direction = Direction::Up;
-                    self.colour.value = 0.0;
-                }
-            }
-        }
-        let px = crate::utils::hsv_to_rgb8_pixel(self.colour);
-        let len = core::cmp::min(N, buf.len());
-        for slot in buf.iter_mut().take(len) { *slot = px; }
-        Some(len)
+    fn next_line(&mut self, buf: &mut [RGB8], dt: u32) -> Option<usize> {
+        self.next_line_as(buf, dt)
     }
 
     fn pixel_count(&self) -> usize {
@@ -67,61 +421,169 @@ impl<const N: usize> EffectIterator for Breathe<N> {
     }
 }
 
-/// Random Breathe: picks a new random hue when the cycle resets.
-pub struct BreatheRandom<const N: usize, R: RngCore> {
+impl<const N: usize> RenderAs for Breathe<N> {
+    fn next_line_as<P: Pixel>(&mut self, buf: &mut [P], dt: u32) -> Option<usize> {
+        self.advance(dt);
+        Some(self.render(buf))
+    }
+}
+
+/// Random Breathe: picks a new random hue, or palette colour, when the cycle resets.
+pub struct BreatheRandom<const N: usize, R: RngCore = FastRng> {
+    #[cfg(not(feature = "int-math"))]
     colour: Hsv,
+    #[cfg(feature = "int-math")]
+    peak: RGB8,
+    palette: Option<&'static [Srgb]>,
+    /// Start and width of the hue range, in 1/65536ths of a turn.
+    hue_start: u16,
+    hue_width: u16,
     rng: R,
-    direction: Direction,
-    step: f32,
+    ramp: Ramp,
 }
 
 impl<const N: usize, R: RngCore> BreatheRandom<N, R> {
     const DEFAULT_STEP: f32 = 0.02;
-    pub fn new_random(mut rng: R, step_size: Option<f32>) -> Self {
-        // initial random hue
-        let hue = (rng.next_u32() as f32 / u32::MAX as f32) * 360.0;
-        let mut colour = Hsv::new(hue, 1.0, 1.0);
-        colour.value = 0.0;
-        Self {
-            colour,
+    pub fn new_random(rng: R, step_size: Option<f32>) -> Self {
+        let mut me = Self {
+            #[cfg(not(feature = "int-math"))]
+            colour: Hsv::new(0.0, 1.0, 1.0),
+            #[cfg(feature = "int-math")]
+            peak: RGB8 { r: 0, g: 0, b: 0 },
+            palette: None,
+            hue_start: 0,
+            hue_width: u16::MAX,
             rng,
-            direction: Direction::Up,
-            step: step_size.unwrap_or(Self::DEFAULT_STEP),
-        }
+            ramp: Ramp::new(step_size.unwrap_or(Self::DEFAULT_STEP)),
+        };
+        me.random_colour();
+        me
     }
-}
 
-impl<const N: usize, R: RngCore> EffectIterator for BreatheRandom<N, R> {
-    fn name(&self) -> &'static str {
-        "Breathe"
+    /// See [Breathe::set_period].
+    pub fn set_period(&mut self, period_ticks: Option<u32>) {
+        self.ramp.set_period(period_ticks);
     }
 
-    fn next_line(&mut self, buf: &mut [RGB8], _dt: u32) -> Option<usize> {
-        match self.direction {
-            Direction::Up => {
-                self.colour.value += self.step;
-                if self.colour.value >= 1.0 {
-                    self.direction = Direction::Down;
-                }
+    /// See [Breathe::set_phase].
+    pub fn set_phase(&mut self, phase: f32) {
+        self.ramp.set_phase(phase);
+    }
+
+    /// See [Breathe::set_wave].
+    pub fn set_wave(&mut self, wave: BreatheWave) {
+        self.ramp.wave = wave;
+    }
+
+    /// See [Breathe::set_range].
+    pub fn set_range(&mut self, min: f32, max: f32) {
+        self.ramp.set_range(min, max);
+    }
+
+    /// See [Breathe::set_hold].
+    pub fn set_hold(&mut self, peak_ticks: u32, trough_ticks: u32) {
+        self.ramp.set_hold(peak_ticks, trough_ticks);
+    }
+
+    /// Pick each breath's colour from `palette` rather than a random hue.
+    pub fn set_palette(&mut self, palette: Option<&'static [Srgb]>) {
+        self.palette = palette.filter(|p| !p.is_empty());
+    }
+
+    /// Limit random hues to `hues` in degrees, the whole wheel by default. Ranges may wrap
+    /// past 360, e.g. `300.0..420.0` for magentas to oranges.
+    pub fn set_hues(&mut self, hues: Range<f32>) {
+        let turns = |degrees: f32| degrees / 360.0 * 65536.0;
+        self.hue_start = turns(hues.start) as i64 as u16;
+        self.hue_width = turns(hues.end - hues.start).clamp(0.0, u16::MAX as f32) as u16;
+    }
+
+    fn random_colour(&mut self) {
+        let random = self.rng.next_u32();
+        self.set_hue(random);
+    }
+
+    /// Set the colour from a random `u32`: a palette entry, or a hue within the range.
+    fn set_hue(&mut self, random: u32) {
+        if let Some(palette) = self.palette {
+            let colour = Hsv::from_color(palette[random as usize % palette.len()]);
+            #[cfg(not(feature = "int-math"))]
+            {
+                self.colour = colour;
             }
-            Direction::Down => {
-                self.colour.value -= self.step;
-                if self.colour.value <= 0.0 {
-                    self.direction = Direction::Up;
-                    // choose new hue
-                    let hue = (self.rng.next_u32() as f32 / u32::MAX as f32) * 360.0;
-                    self.colour = Hsv::new(hue, 1.0, 1.0);
-                    self.colour.value = 0.0;
-                }
+            #[cfg(feature = "int-math")]
+            {
+                self.peak = crate::utils::hsv_to_rgb8_pixel(Hsv::new(colour.hue, colour.saturation, 1.0));
             }
+            return;
+        }
+        let hue = self.hue_start.wrapping_add((((random >> 16) * self.hue_width as u32) >> 16) as u16);
+        #[cfg(not(feature = "int-math"))]
+        {
+            self.colour = Hsv::new(hue as f32 * (360.0 / 65536.0), 1.0, 1.0);
+        }
+        #[cfg(feature = "int-math")]
+        {
+            use crate::math8::{hsv2rgb_rainbow, Hsv8};
+            self.peak = hsv2rgb_rainbow(Hsv8::new((hue >> 8) as u8, 255, 255));
+        }
+    }
+}
+
+impl<const N: usize> BreatheRandom<N> {
+    /// Same as [BreatheRandom::new_random] using a [FastRng] seeded with `seed`.
+    pub fn seeded(seed: u64, step_size: Option<f32>) -> Self {
+        Self::new_random(FastRng::new(seed), step_size)
+    }
+}
+
+impl<const N: usize, R: RngCore> PixelStream for BreatheRandom<N, R> {
+    fn advance(&mut self, dt: u32) {
+        if self.ramp.advance(dt) {
+            self.random_colour();
         }
-        let px = crate::utils::hsv_to_rgb8_pixel(self.colour);
+    }
+
+    fn pixel<P: Pixel>(&self, _index: usize) -> P {
+        #[cfg(not(feature = "int-math"))]
+        return self.ramp.shade(self.colour);
+        #[cfg(feature = "int-math")]
+        return self.ramp.shade(self.peak);
+    }
+
+    fn render<P: Pixel>(&self, buf: &mut [P]) -> usize {
+        let px = self.pixel(0);
         let len = core::cmp::min(N, buf.len());
         for slot in buf.iter_mut().take(len) { *slot = px; }
-        Some(len)
+        len
+    }
+}
+
+/// Each breath's hue is derived from `seed` and the breath number.
+impl<const N: usize, R: RngCore> Seek for BreatheRandom<N, R> {
+    fn seek(&mut self, step: u64, step_ticks: u32, seed: u64) {
+        let cycle = self.ramp.seek_ticks(step, step_ticks);
+        self.set_hue(crate::rng::hash32(seed, cycle));
+    }
+}
+
+impl<const N: usize, R: RngCore> EffectIterator for BreatheRandom<N, R> {
+    fn name(&self) -> &'static str {
+        "Breathe"
+    }
+
+    fn next_line(&mut self, buf: &mut [RGB8], dt: u32) -> Option<usize> {
+        self.next_line_as(buf, dt)
     }
 
     fn pixel_count(&self) -> usize {
         N
     }
 }
+
+impl<const N: usize, R: RngCore> RenderAs for BreatheRandom<N, R> {
+    fn next_line_as<P: Pixel>(&mut self, buf: &mut [P], dt: u32) -> Option<usize> {
+        self.advance(dt);
+        Some(self.render(buf))
+    }
+}
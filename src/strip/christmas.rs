@@ -1,18 +1,129 @@
-use crate::{strip::EffectIterator, RGB8};
+use crate::{
+    rng::{hash32, FastRng, RandomExt},
+    strip::{draw::span, pace::Pace, EffectIterator, RenderAs},
+    Pixel, RGB8,
+};
+use core::f32::consts::TAU;
 use palette::{Mix, Srgb};
 use rand_core::RngCore;
 
+/// Background and palette colours for [Christmas].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ChristmasTheme {
+    pub background: Srgb,
+    /// Colours used by the patterns, in order.
+    pub palette: &'static [Srgb],
+}
+
+impl ChristmasTheme {
+    const RED: Srgb = Srgb::new(1.0, 0.0, 0.0);
+    const GREEN: Srgb = Srgb::new(0.0, 1.0, 0.0);
+    const BLUE: Srgb = Srgb::new(0.0, 0.0, 1.0);
+    const WHITE: Srgb = Srgb::new(1.0, 1.0, 1.0);
+    const BLACK: Srgb = Srgb::new(0.0, 0.0, 0.0);
+
+    /// Green background with red, green, blue, gold and cyan lights.
+    pub const CHRISTMAS: ChristmasTheme = ChristmasTheme {
+        background: Srgb::new(6.0 / 255.0, 108.0 / 255.0, 22.0 / 255.0),
+        palette: &[
+            Self::RED,
+            Self::GREEN,
+            Self::BLUE,
+            Srgb::new(1.0, 0.7, 0.0),
+            Srgb::new(0.0, 0.84, 1.0),
+        ],
+    };
+    /// Dark purple background with orange, purple and lime lights.
+    pub const HALLOWEEN: ChristmasTheme = ChristmasTheme {
+        background: Srgb::new(0.08, 0.0, 0.12),
+        palette: &[
+            Srgb::new(1.0, 0.35, 0.0),
+            Srgb::new(0.6, 0.0, 1.0),
+            Srgb::new(0.5, 1.0, 0.0),
+        ],
+    };
+    /// Deep red background with red, pink and white lights.
+    pub const VALENTINE: ChristmasTheme = ChristmasTheme {
+        background: Srgb::new(0.15, 0.0, 0.03),
+        palette: &[Self::RED, Srgb::new(1.0, 0.3, 0.5), Self::WHITE],
+    };
+    pub const FRANCE: ChristmasTheme = Self::flag(&[Self::BLUE, Self::WHITE, Self::RED]);
+    pub const ITALY: ChristmasTheme = Self::flag(&[Self::GREEN, Self::WHITE, Self::RED]);
+    pub const IRELAND: ChristmasTheme = Self::flag(&[Self::GREEN, Self::WHITE, Srgb::new(1.0, 0.45, 0.0)]);
+
+    /// National flag stripes on black, best shown with [ChristmasPattern::Bands].
+    pub const fn flag(stripes: &'static [Srgb]) -> ChristmasTheme {
+        ChristmasTheme {
+            background: Self::BLACK,
+            palette: stripes,
+        }
+    }
+
+    fn colour(&self, index: usize) -> Srgb {
+        match self.palette.len() {
+            0 => self.background,
+            len => self.palette[index % len],
+        }
+    }
+}
+
+impl Default for ChristmasTheme {
+    fn default() -> Self {
+        Self::CHRISTMAS
+    }
+}
+
+/// What [Christmas] draws.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ChristmasPattern {
+    /// Random palette sparkles fading over the background.
+    #[default]
+    Sparkle,
+    /// Blocks of `width` pixels alternating between the first two palette colours, stepping
+    /// along the strip.
+    Chase { width: usize },
+    /// Old fashioned C9 bulbs: every pixel a palette colour in turn, each gently twinkling.
+    Bulbs,
+    /// Red and white stripes drifting slowly along the strip.
+    CandyCane,
+    /// White flakes drifting slowly from the far end down to index 0 over the background.
+    Snowfall,
+    /// The strip split into equal bands of the palette colours, e.g. flag stripes.
+    Bands,
+}
+
+/// A sparkle or snowflake.
 pub struct Sparkle {
     colour: Srgb,
     intensity: f32,
-    location: usize,
+    position: f32,
+    speed: f32, /* pixels per a second */
 }
 
-pub struct Christmas<const N: usize, const S: usize, R: RngCore> {
+/// Seasonal lights: sparkles, chases, twinkling bulbs, candy-cane stripes, snowfall and flag
+/// bands in a [ChristmasTheme].
+///
+/// # Arguments
+///
+/// - `N` - The number of LEDs in the strip.
+/// - `S` - The most sparkles or snowflakes shown at once. When all are in use a new sparkle
+///   replaces the faintest one.
+/// - `rng` - The random number source.
+/// - `sparkle` - Upper bound for the number of new sparkles tried each frame.
+/// - `probability` - The chance of each try producing a sparkle.
+/// - `fade` - How much a sparkle fades each frame.
+pub struct Christmas<const N: usize, const S: usize, R: RngCore = FastRng> {
     frequency: u8,
     probability: f32,
     fade: f32,
     sparkles: [Option<Sparkle>; S],
+    theme: ChristmasTheme,
+    pattern: ChristmasPattern,
+    offset: usize,
+    pace: Pace,
+    /// Milliseconds into [Self::LOOP_MS], so the phase stays accurate however long it runs.
+    time: u32,
+    seed: u64,
     rng: R,
 }
 
@@ -20,51 +131,155 @@ impl<const N: usize, const S: usize, R: RngCore> Christmas<N, S, R> {
     const DEFAULT_FREQUENCY: u8 = 0x04;
     const DEFAULT_PROBABILITY: f32 = 0.1;
     const DEFAULT_FADE: f32 = 0.4;
-    const BACKGROUND: Srgb = Srgb::new(6.0 / 255.0, 108.0 / 255.0, 22.0 / 255.0);
+    const DEFAULT_STEP_TICKS: u32 = 500;
+    const CANDY_WIDTH: f32 = 3.0;
+    const CANDY_SPEED: f32 = 2.0;
+    /// Time for the stripes to move on by one red and one white stripe.
+    const CANDY_PERIOD_MS: u32 = (2.0 * Self::CANDY_WIDTH / Self::CANDY_SPEED * 1000.0) as u32;
+    /// Every bulb twinkles a whole number of times in this.
+    const BULB_PERIOD_MS: u32 = 100_000;
+    /// A whole number of both the bulb and candy-cane periods.
+    const LOOP_MS: u32 = 300_000;
+    const SNOW: Srgb = Srgb::new(1.0, 1.0, 1.0);
 
-    pub fn new(rng: R, sparkle: Option<u8>, probability: Option<f32>, fade: Option<f32>) -> Self {
+    pub fn new(mut rng: R, sparkle: Option<u8>, probability: Option<f32>, fade: Option<f32>) -> Self {
+        let mut pace = Pace::default();
+        pace.set_ticks(Some(Self::DEFAULT_STEP_TICKS));
         Self {
             frequency: sparkle.unwrap_or(Self::DEFAULT_FREQUENCY),
             fade: fade.unwrap_or(Self::DEFAULT_FADE),
             probability: probability.unwrap_or(Self::DEFAULT_PROBABILITY),
             sparkles: core::array::from_fn(|_| None),
+            theme: ChristmasTheme::default(),
+            pattern: ChristmasPattern::default(),
+            offset: 0,
+            pace,
+            time: 0,
+            seed: rng.next_u64(),
             rng,
         }
     }
 
+    pub fn set_theme(&mut self, theme: ChristmasTheme) {
+        self.theme = theme;
+    }
+
+    pub fn set_pattern(&mut self, pattern: ChristmasPattern) {
+        self.pattern = pattern;
+        self.sparkles = core::array::from_fn(|_| None);
+    }
+
+    /// Time between steps of [ChristmasPattern::Chase], 500 by default.
+    pub fn set_step_ticks(&mut self, ticks: u32) {
+        self.pace.set_ticks(Some(ticks));
+    }
+
+    /// Put a sparkle at `position`, refreshing one already there rather than stacking a
+    /// second on the same pixel. Snowflakes only take free slots, so none vanish mid-fall.
+    fn add_sparkle(&mut self, sparkle: Sparkle) {
+        if sparkle.speed > 0.0 {
+            if let Some(slot) = self.sparkles.iter_mut().find(|s| s.is_none()) {
+                *slot = Some(sparkle);
+            }
+            return;
+        }
+        let index = sparkle.position as usize;
+        let existing = self.sparkles.iter().position(|s| {
+            matches!(s, Some(s) if s.position as usize == index)
+        });
+        let free = || self.sparkles.iter().position(|s| s.is_none());
+        let faintest = || {
+            (0..S).min_by(|&a, &b| {
+                let intensity = |i: usize| self.sparkles[i].as_ref().map_or(0.0, |s| s.intensity);
+                intensity(a).total_cmp(&intensity(b))
+            })
+        };
+        if let Some(slot) = existing.or_else(free).or_else(faintest) {
+            self.sparkles[slot] = Some(sparkle);
+        }
+    }
+
     fn generate_sparkle(&mut self) {
-        let chance = (self.rng.next_u32() as f32) / (u32::MAX as f32 + 1.0);
-        if chance > self.probability {
+        if N == 0 || !self.rng.chance(self.probability) {
             return;
         }
-        let index = (self.rng.next_u32() as usize) % N;
-        let c_index = (self.rng.next_u32() as f32) / (u32::MAX as f32 + 1.0);
-        let colour = if c_index < 0.5 {
-            Srgb::new(1.0, 0.0, 0.0)
-        } else if c_index < 0.80 {
-            Srgb::new(0.0, 0.84, 1.0)
+        let sparkle = if self.pattern == ChristmasPattern::Snowfall {
+            Sparkle {
+                colour: Self::SNOW,
+                intensity: self.rng.range_f32(0.4..1.0),
+                position: N as f32,
+                speed: self.rng.range_f32(1.5..4.0),
+            }
         } else {
-            Srgb::new(0.0, 0.0, 1.0)
-        };
-        // insert into first free slot
-        if let Some(slot) = self.sparkles.iter_mut().find(|s| s.is_none()) {
-            *slot = Some(Sparkle {
-                colour,
+            let len = self.theme.palette.len().max(1);
+            Sparkle {
+                colour: self.theme.colour(self.rng.index(len)),
                 intensity: 1.0,
-                location: index,
-            });
-        }
+                position: self.rng.index(N) as f32,
+                speed: 0.0,
+            }
+        };
+        self.add_sparkle(sparkle);
     }
 
-    fn fade_sparkles(&mut self) {
+    fn update_sparkles(&mut self, dt_sec: f32) {
         for s in self.sparkles.iter_mut() {
             if let Some(sp) = s {
-                sp.intensity -= self.fade;
-                if sp.intensity <= 0.0 {
+                let gone = if sp.speed > 0.0 {
+                    sp.position -= sp.speed * dt_sec;
+                    sp.position < -1.0
+                } else {
+                    sp.intensity -= self.fade;
+                    sp.intensity <= 0.0
+                };
+                if gone {
                     *s = None;
                 }
             }
         }
+        let chances = (self.rng.next_u32() % self.frequency.max(1) as u32) as u8;
+        for _ in 0..chances {
+            self.generate_sparkle();
+        }
+    }
+
+    fn draw_sparkles<P: Pixel>(&self, buf: &mut [P]) {
+        let background = self.theme.background;
+        for slot in buf.iter_mut() { *slot = P::from_srgb(background); }
+        for s in self.sparkles.iter().filter_map(|x| x.as_ref()) {
+            if s.speed > 0.0 {
+                span(buf, s.position, s.position + 1.0, s.colour * s.intensity);
+            } else if let Some(slot) = buf.get_mut(s.position as usize) {
+                *slot = P::from_srgb(background.mix(s.colour, s.intensity));
+            }
+        }
+    }
+
+    /// Brightness of C9 bulb `index`, each twinkling at its own rate and phase.
+    fn bulb_brightness(&self, index: usize) -> f32 {
+        let hash = hash32(self.seed, index as u64);
+        let rate = 20 + (hash & 0xffff) % 41; /* twinkles per a period, 0.2 to 0.6 a second */
+        let phase = (hash >> 16) as f32 / 65535.0;
+        let period = Self::BULB_PERIOD_MS;
+        let turns = (rate * (self.time % period) % period) as f32 / period as f32 + phase;
+        0.55 + 0.45 * libm::sinf(turns * TAU)
+    }
+
+    /// How much of pixel `index` is covered by the red candy-cane stripes.
+    fn candy_red(&self, index: usize) -> f32 {
+        let width = Self::CANDY_WIDTH;
+        let period = Self::CANDY_PERIOD_MS;
+        let shift = (self.time % period) as f32 / period as f32 * 2.0 * width;
+        let low = (index as f32 + shift) % (2.0 * width);
+        let overlap = |from: f32, to: f32| (to.min(low + 1.0) - from.max(low)).max(0.0);
+        overlap(0.0, width) + overlap(2.0 * width, 3.0 * width)
+    }
+}
+
+impl<const N: usize, const S: usize> Christmas<N, S> {
+    /// Same as [Christmas::new] using a [FastRng] seeded with `seed`.
+    pub fn seeded(seed: u64, sparkle: Option<u8>, probability: Option<f32>, fade: Option<f32>) -> Self {
+        Self::new(FastRng::new(seed), sparkle, probability, fade)
     }
 }
 
@@ -73,34 +288,55 @@ impl<const N: usize, const S: usize, R: RngCore> EffectIterator for Christmas<N,
         "Christmas"
     }
 
-    fn next_line(&mut self, buf: &mut [RGB8], _dt: u32) -> Option<usize> {
-        self.fade_sparkles();
-        let chances = (self.rng.next_u32() % self.frequency as u32) as u8;
-        for _ in 0..chances {
-            self.generate_sparkle();
-        }
-        let len = core::cmp::min(N, buf.len());
-        // background
-        let base: Srgb<u8> = Self::BACKGROUND.into_format();
-        for slot in buf.iter_mut().take(len) {
-            *slot = RGB8 { r: base.red, g: base.green, b: base.blue };
-        }
-        // add sparkles by mixing onto background
-        for s in self.sparkles.iter().filter_map(|x| x.as_ref()) {
-            if s.location < len {
-                let bg: Srgb = Self::BACKGROUND;
-                let mixed: Srgb<u8> = bg.mix(s.colour, s.intensity).into_format();
-                buf[s.location] = RGB8 {
-                    r: mixed.red,
-                    g: mixed.green,
-                    b: mixed.blue,
-                };
-            }
-        }
-        Some(len)
+    fn next_line(&mut self, buf: &mut [RGB8], dt: u32) -> Option<usize> {
+        self.next_line_as(buf, dt)
     }
 
     fn pixel_count(&self) -> usize {
         N
     }
 }
+
+impl<const N: usize, const S: usize, R: RngCore> RenderAs for Christmas<N, S, R> {
+    fn next_line_as<P: Pixel>(&mut self, buf: &mut [P], dt: u32) -> Option<usize> {
+        self.time = (self.time + dt % Self::LOOP_MS) % Self::LOOP_MS;
+        let len = core::cmp::min(N, buf.len());
+        let buf = &mut buf[..len];
+        match self.pattern {
+            ChristmasPattern::Sparkle | ChristmasPattern::Snowfall => {
+                self.update_sparkles(dt as f32 / 1000.0);
+                self.draw_sparkles(buf);
+            }
+            ChristmasPattern::Chase { width } => {
+                let width = width.max(1);
+                self.offset = (self.offset + self.pace.steps(dt, 1)) % (2 * width);
+                let colours = [
+                    self.theme.colour(0),
+                    self.theme.palette.get(1).copied().unwrap_or(self.theme.background),
+                ];
+                for (i, slot) in buf.iter_mut().enumerate() {
+                    let block = (i + 2 * width - self.offset) / width % 2;
+                    *slot = P::from_srgb(colours[block]);
+                }
+            }
+            ChristmasPattern::Bulbs => {
+                for (i, slot) in buf.iter_mut().enumerate() {
+                    *slot = P::from_srgb(self.theme.colour(i) * self.bulb_brightness(i));
+                }
+            }
+            ChristmasPattern::CandyCane => {
+                for (i, slot) in buf.iter_mut().enumerate() {
+                    let white = Srgb::new(1.0, 1.0, 1.0);
+                    *slot = P::from_srgb(white.mix(Srgb::new(1.0, 0.0, 0.0), self.candy_red(i)));
+                }
+            }
+            ChristmasPattern::Bands => {
+                let bands = self.theme.palette.len().max(1);
+                for (i, slot) in buf.iter_mut().enumerate() {
+                    *slot = P::from_srgb(self.theme.colour(i * bands / N));
+                }
+            }
+        }
+        Some(len)
+    }
+}
@@ -1,204 +1,342 @@
-use crate::{strip::EffectIterator, RGB8};
-use palette::{Darken, FromColor, Hsv, Mix, Srgb};
+use crate::{
+    rng::{FastRng, RandomExt},
+    strip::{
+        draw::{blend, span},
+        EffectIterator, RenderAs,
+    },
+    Pixel, RGB8,
+};
+use core::ops::Range;
+use palette::{FromColor, Hsv, Mix, Srgb};
 use rand_core::RngCore;
 
+/// Most debris fragments alive at once.
+const MAX_DEBRIS: usize = 32;
+
+/// One of the particles of a [Collision].
 #[derive(Debug, Clone, Copy)]
 pub struct Particle {
-    position: i32,
+    position: f32, /* centre, in pixels */
+    previous: f32, /* centre before the last move */
+    velocity: f32, /* pixels per a second */
+    size: f32,
     colour: Srgb,
-    reverse: bool,
-    speed: usize,
-    size: usize,
+    alive: bool,
 }
 
 impl Particle {
+    const DEFAULT_SPEEDS: Range<f32> = 15.0..40.0;
+    const DEFAULT_SIZES: Range<f32> = 1.0..3.0;
+
+    /// A particle at `position` with a random hue, size and speed, moving up the strip or, if
+    /// `reverse`, down it.
     pub fn new<R: RngCore>(position: i32, reverse: bool, rng: &mut R) -> Self {
-        let h = (rng.next_u32() as f32) / (u32::MAX as f32 + 1.0) * 360.0;
-        let size = 1 + (rng.next_u32() as usize % 3);
+        let colour = Srgb::from_color(Hsv::new(rng.range_f32(0.0..360.0), 1.0, 1.0));
+        let size = rng.range_f32(Self::DEFAULT_SIZES);
+        let speed = rng.range_f32(Self::DEFAULT_SPEEDS);
+        let velocity = if reverse { -speed } else { speed };
+        Self::with_velocity(position as f32, velocity, size, colour)
+    }
+
+    /// A particle centred on `position`, moving at `velocity` pixels per second.
+    pub fn with_velocity(position: f32, velocity: f32, size: f32, colour: Srgb) -> Self {
         Particle {
             position,
-            colour: Srgb::from_color(Hsv::new(h, 1.0, 1.0)),
-            reverse,
-            speed: 1,
+            previous: position,
+            velocity,
             size,
+            colour,
+            alive: true,
         }
     }
 
-    pub fn collide(&self, other: &Particle) -> Option<(Particle, Particle)> {
-        if (self.position - other.position).abs() > 1 || self.position - other.position < -1 {
+    /// Mass for collisions, proportional to size.
+    fn mass(&self) -> f32 {
+        self.size.max(0.01)
+    }
+
+    /// If the particles touch and are approaching, or passed through each other in their last
+    /// move, the point of impact.
+    pub fn impact(&self, other: &Particle) -> Option<f32> {
+        let before = other.previous - self.previous;
+        let distance = other.position - self.position;
+        let crossed = before * distance < 0.0;
+        let touching = distance.abs() <= (self.size + other.size) / 2.0;
+        let side = if crossed { before } else { distance };
+        let approaching = (other.velocity - self.velocity) * side < 0.0;
+        if !(self.alive && other.alive && (touching || crossed) && approaching) {
             return None;
         }
-        let mut rhs = *other;
-        let mut lhs = *self;
-        lhs.reverse = !lhs.reverse;
-        rhs.reverse = !rhs.reverse;
+        if crossed {
+            // where the centres met
+            let fraction = before / (before - distance);
+            return Some(self.previous + (self.position - self.previous) * fraction);
+        }
+        // where the surfaces meet
+        let radius = if distance > 0.0 { self.size } else { -self.size } / 2.0;
+        Some(self.position + radius)
+    }
+
+    /// Put the particles side by side, touching at `at`, on the sides they came from.
+    fn touch_at(&mut self, other: &mut Particle, at: f32) {
+        let side = if other.previous > self.previous { 1.0 } else { -1.0 };
+        self.position = at - side * self.size / 2.0;
+        other.position = at + side * other.size / 2.0;
+    }
+
+    /// Elastic collision conserving momentum and energy; the colours blend towards the
+    /// heavier particle.
+    pub fn collide(&mut self, other: &mut Particle) {
+        let (ma, mb) = (self.mass(), other.mass());
+        let (va, vb) = (self.velocity, other.velocity);
+        self.velocity = ((ma - mb) * va + 2.0 * mb * vb) / (ma + mb);
+        other.velocity = ((mb - ma) * vb + 2.0 * ma * va) / (ma + mb);
+
+        let mix = self.colour.mix(other.colour, mb / (ma + mb));
+        self.colour = self.colour.mix(mix, 0.5);
+        other.colour = other.colour.mix(mix, 0.5);
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Debris {
+    position: f32,
+    velocity: f32,
+    colour: Srgb,
+    life: f32,
+    lifetime: f32,
+}
 
-        let scaling_factor = 1.0 - lhs.size as f32 / (lhs.size + rhs.size) as f32;
-        let mix = lhs.colour.mix(rhs.colour, scaling_factor / 2.0);
-        lhs.colour = mix;
-        rhs.colour = mix;
+impl Debris {
+    const DEAD: Debris = Debris {
+        position: 0.0,
+        velocity: 0.0,
+        colour: Srgb::new(0.0, 0.0, 0.0),
+        life: 0.0,
+        lifetime: 1.0,
+    };
+    /// Fraction of its speed a fragment keeps per second.
+    const DRAG: f32 = 0.3;
 
-        Some((lhs, rhs))
+    fn alive(&self) -> bool {
+        self.life > 0.0
     }
 }
 
-pub struct Collision<const N: usize, R: RngCore> {
-    particles: [Particle; 2],
+/// Particles fly in from both ends of the strip and either bounce off each other, or shatter
+/// into fading debris at the point of impact.
+///
+/// Collisions conserve momentum with masses proportional to size, and debris carries on with
+/// the momentum of the pair. Speeds are in pixels per second with `dt_ticks` in milliseconds.
+///
+/// # Arguments
+///
+/// - `N` - The number of LEDs in the strip.
+/// - `R` - The random number source type, [FastRng] by default.
+/// - `M` - The number of particles, 2 by default. Even particles start at index 0, odd ones
+///   at the far end.
+/// - `rng` - The random number source.
+/// - `shatter` - Whether particles shatter on impact (the default) or bounce.
+pub struct Collision<const N: usize, R: RngCore = FastRng, const M: usize = 2> {
+    particles: [Particle; M],
+    colours: [Option<Srgb>; M],
+    debris: [Debris; MAX_DEBRIS],
+    debris_colour: Option<Srgb>,
+    speeds: Range<f32>,
+    sizes: Range<f32>,
     shatter: bool,
-    shattered: bool,
-    current: [Srgb; N],
     rng: R,
 }
 
-impl<const N: usize, R: RngCore> Collision<N, R> {
-    pub fn new(mut rng: R, shatter: Option<bool>) -> Self {
-        let p1 = Particle::new(0, false, &mut rng);
-        let p2 = Particle::new(N as i32, true, &mut rng);
-        Self {
-            particles: [p1, p2],
+impl<const N: usize, R: RngCore, const M: usize> Collision<N, R, M> {
+    /// Fragments per pixel of particle size.
+    const DEBRIS_PER_SIZE: f32 = 4.0;
+    const FLASH: Srgb = Srgb::new(1.0, 1.0, 1.0);
+
+    pub fn new(rng: R, shatter: Option<bool>) -> Self {
+        let mut me = Self {
+            particles: [Particle::with_velocity(0.0, 0.0, 1.0, Self::FLASH); M],
+            colours: [None; M],
+            debris: [Debris::DEAD; MAX_DEBRIS],
+            debris_colour: None,
+            speeds: Particle::DEFAULT_SPEEDS,
+            sizes: Particle::DEFAULT_SIZES,
             shatter: shatter.unwrap_or(true),
-            shattered: false,
-            current: [Srgb::new(0.0, 0.0, 0.0); N],
             rng,
-        }
+        };
+        me.reset();
+        me
     }
 
+    /// Launch a new set of particles.
     pub fn reset(&mut self) {
-        let p1 = Particle::new(0, false, &mut self.rng);
-        let p2 = Particle::new(N as i32 - 1, true, &mut self.rng);
-        self.particles = [p1, p2];
-        self.shattered = false;
+        for i in 0..M {
+            let size = self.rng.range_f32(self.sizes.clone());
+            let speed = self.rng.range_f32(self.speeds.clone());
+            // stagger the launches so the particles arrive at different times
+            let offset = (i / 2) as f32 * N as f32 / M.max(1) as f32 + size / 2.0;
+            let (position, velocity) = if i % 2 == 0 {
+                (-offset, speed)
+            } else {
+                (N as f32 + offset, -speed)
+            };
+            let colour = self.colours[i].unwrap_or_else(|| {
+                Srgb::from_color(Hsv::new(self.rng.range_f32(0.0..360.0), 1.0, 1.0))
+            });
+            self.particles[i] = Particle::with_velocity(position, velocity, size, colour);
+        }
+        self.debris = [Debris::DEAD; MAX_DEBRIS];
     }
 
-    pub fn check_for_collision(&mut self) -> bool {
-        if let Some((lhs, rhs)) = self.particles[0].collide(&self.particles[1]) {
-            self.particles[0] = lhs;
-            self.particles[1] = rhs;
-
-            true
-        } else {
-            false
+    /// Colour of particle `index`, or None for a random hue on every launch.
+    pub fn set_colour(&mut self, index: usize, colour: Option<Srgb>) {
+        if let Some(c) = self.colours.get_mut(index) {
+            *c = colour;
         }
     }
 
-    pub fn shatter(&mut self) {
-        if !self.shatter {
-            return;
-        }
-        self.shattered = true;
+    /// Colour of all debris, or None to keep the colour of the particle it came from.
+    pub fn set_debris_colour(&mut self, colour: Option<Srgb>) {
+        self.debris_colour = colour;
+    }
 
-        self.current[N / 2] = Srgb::new(1.0, 1.0, 1.0);
+    /// Launch speeds in pixels per second, 15 to 40 by default.
+    pub fn set_speeds(&mut self, speeds: Range<f32>) {
+        self.speeds = speeds;
+    }
 
-        let mut hsv = Hsv::from_color(self.particles[0].colour);
-        hsv.value = 1.0;
-        let normalize = 1.0 / N as f32;
+    /// Particle sizes in pixels, 1 to 3 by default.
+    pub fn set_sizes(&mut self, sizes: Range<f32>) {
+        self.sizes = sizes;
+    }
 
-        for i in 0..(N / 2) {
-            if (self.rng.next_u32() & 1) == 1 {
-                let hsv = hsv.darken(1.0 - normalize * i as f32);
-                self.current[i] = Srgb::from_color(hsv);
-            }
-        }
-        for i in (N / 2)..(N) {
-            if (self.rng.next_u32() & 1) == 1 {
-                let hsv = hsv.darken(normalize * i as f32);
-                self.current[i] = Srgb::from_color(hsv);
+    /// Resolve collisions between all pairs. Returns true if any happened.
+    pub fn check_for_collision(&mut self) -> bool {
+        let mut collided = false;
+        for i in 0..M {
+            let (head, tail) = self.particles.split_at_mut(i + 1);
+            let a = &mut head[i];
+            for b in tail.iter_mut() {
+                let Some(at) = a.impact(b) else {
+                    continue;
+                };
+                collided = true;
+                if self.shatter {
+                    Self::shatter(&mut self.debris, self.debris_colour, &mut self.rng, a, b, at);
+                } else {
+                    a.touch_at(b, at);
+                    a.collide(b);
+                }
             }
         }
+        collided
     }
 
-    pub fn move_particles(&mut self, out: &mut [RGB8]) {
-        for pixel in out.iter_mut() {
-            *pixel = RGB8 { r: 0, g: 0, b: 0 };
+    /// Replace both particles with debris flying out from `at`.
+    fn shatter(
+        debris: &mut [Debris],
+        debris_colour: Option<Srgb>,
+        rng: &mut R,
+        a: &mut Particle,
+        b: &mut Particle,
+        at: f32,
+    ) {
+        let (ma, mb) = (a.mass(), b.mass());
+        // the debris as a whole keeps the pair's momentum
+        let centre_velocity = (ma * a.velocity + mb * b.velocity) / (ma + mb);
+        let spread = (a.velocity - b.velocity).abs();
+
+        let mut free = debris.iter_mut().filter(|d| !d.alive());
+        if let Some(flash) = free.next() {
+            *flash = Debris {
+                position: at,
+                velocity: centre_velocity,
+                colour: Self::FLASH,
+                life: 0.2,
+                lifetime: 0.2,
+            };
         }
-        for particle in self.particles.iter_mut() {
-            if particle.position >= 0 && particle.position < N as i32 {
-                for i in 0..particle.size {
-                    if particle.reverse {
-                        if particle.position + i as i32 >= 0
-                            && i as i32 + particle.position < N as i32
-                        {
-                            let p = particle.colour.into_format::<u8>();
-                            out[(particle.position + i as i32) as usize] = RGB8 {
-                                r: p.red,
-                                g: p.green,
-                                b: p.blue,
-                            };
-                        }
-                    } else if particle.position - i as i32 >= 0
-                        && (particle.position - i as i32) < N as i32
-                    {
-                        let p = particle.colour.into_format::<u8>();
-                        out[(particle.position - i as i32) as usize] = RGB8 {
-                            r: p.red,
-                            g: p.green,
-                            b: p.blue,
-                        };
-                    }
-                }
-                let p = particle.colour.into_format::<u8>();
-                out[particle.position as usize] = RGB8 {
-                    r: p.red,
-                    g: p.green,
-                    b: p.blue,
+        for particle in [&*a, &*b] {
+            let count = (particle.size * Self::DEBRIS_PER_SIZE) as usize;
+            for fragment in free.by_ref().take(count) {
+                let lifetime = rng.range_f32(0.5..1.5);
+                *fragment = Debris {
+                    position: at,
+                    velocity: centre_velocity + rng.range_f32(-spread..spread),
+                    colour: debris_colour.unwrap_or(particle.colour),
+                    life: lifetime,
+                    lifetime,
                 };
             }
         }
+        a.alive = false;
+        b.alive = false;
     }
-}
 
-impl<const N: usize, R: RngCore> EffectIterator for Collision<N, R> {
-    fn name(&self) -> &'static str {
-        "Collision"
-    }
-
-    fn next_line(&mut self, buf: &mut [RGB8], _dt: u32) -> Option<usize> {
-        if !self.shattered {
-            for particle in self.particles.iter_mut() {
-                if particle.reverse {
-                    particle.position -= particle.speed as i32;
-                } else {
-                    particle.position += particle.speed as i32;
-                }
-            }
-
-            if self.check_for_collision() {
-                self.shatter();
-            }
-
-            if self.particles[0].position < 0 && self.particles[1].position >= N as i32 {
-                self.reset();
-            }
-            let len = core::cmp::min(N, buf.len());
-            self.move_particles(&mut buf[..len]);
-            Some(len)
-        } else {
-            for pixel in self.current.iter_mut() {
-                if (self.rng.next_u32() & 1) == 1 {
-                    *pixel = pixel.darken(0.1);
-                }
+    fn update(&mut self, dt_sec: f32) {
+        for particle in self.particles.iter_mut().filter(|p| p.alive) {
+            particle.previous = particle.position;
+            particle.position += particle.velocity * dt_sec;
+            let half = particle.size / 2.0;
+            // a particle that has crossed the whole strip is gone
+            let gone = (particle.velocity > 0.0 && particle.position - half > N as f32)
+                || (particle.velocity < 0.0 && particle.position + half < 0.0);
+            if gone {
+                particle.alive = false;
             }
+        }
+        self.check_for_collision();
 
-            const RESET_VAL: f32 = 0.01;
+        let keep = libm::powf(Debris::DRAG, dt_sec);
+        for fragment in self.debris.iter_mut().filter(|d| d.alive()) {
+            fragment.position += fragment.velocity * dt_sec;
+            fragment.velocity *= keep;
+            fragment.life -= dt_sec;
+        }
 
-            for pixel in self.current.iter() {
-                if pixel.red > RESET_VAL || pixel.green > RESET_VAL || pixel.blue > RESET_VAL {
-                    let len = core::cmp::min(N, buf.len());
-                    for (i, slot) in buf.iter_mut().enumerate().take(len) {
-                        let p = self.current[i].into_format::<u8>();
-                        *slot = RGB8 { r: p.red, g: p.green, b: p.blue };
-                    }
-                    return Some(len);
-                }
-            }
+        let active = self.particles.iter().any(|p| p.alive) || self.debris.iter().any(|d| d.alive());
+        if !active {
             self.reset();
-            let len = core::cmp::min(N, buf.len());
-            for slot in buf.iter_mut().take(len) { *slot = RGB8 { r:0, g:0, b:0 }; }
-            Some(len)
         }
     }
+}
+
+impl<const N: usize, const M: usize> Collision<N, FastRng, M> {
+    /// Same as [Collision::new] using a [FastRng] seeded with `seed`.
+    pub fn seeded(seed: u64, shatter: Option<bool>) -> Self {
+        Self::new(FastRng::new(seed), shatter)
+    }
+}
+
+impl<const N: usize, R: RngCore, const M: usize> EffectIterator for Collision<N, R, M> {
+    fn name(&self) -> &'static str {
+        "Collision"
+    }
+
+    fn next_line(&mut self, buf: &mut [RGB8], dt_ticks: u32) -> Option<usize> {
+        self.next_line_as(buf, dt_ticks)
+    }
 
     fn pixel_count(&self) -> usize {
         N
     }
 }
+
+impl<const N: usize, R: RngCore, const M: usize> RenderAs for Collision<N, R, M> {
+    fn next_line_as<P: Pixel>(&mut self, buf: &mut [P], dt_ticks: u32) -> Option<usize> {
+        self.update(dt_ticks as f32 / 1000.0);
+
+        let len = core::cmp::min(N, buf.len());
+        let buf = &mut buf[..len];
+        for slot in buf.iter_mut() { *slot = P::BLACK; }
+        for fragment in self.debris.iter().filter(|d| d.alive()) {
+            let brightness = fragment.life / fragment.lifetime;
+            blend(buf, libm::floorf(fragment.position) as i32, fragment.colour, brightness);
+        }
+        for particle in self.particles.iter().filter(|p| p.alive) {
+            let half = particle.size / 2.0;
+            span(buf, particle.position - half, particle.position + half, particle.colour);
+        }
+        Some(len)
+    }
+}
@@ -1,47 +1,74 @@
-use crate::{strip::EffectIterator, RGB8};
-use palette::{FromColor, Hsv, Srgb};
+use crate::{
+    strip::{EffectIterator, PixelStream, RenderAs, Seek},
+    Pixel, RGB8,
+};
 
+/// Whole strip rotates through the hue wheel. The hue is a 16-bit fraction of a turn.
 pub struct Cycle<const N: usize> {
-    hue: f32,
-    step_size: f32,
+    hue: u16,
+    step_size: u16,
 }
 
 impl<const N: usize> Cycle<N> {
     pub fn new(steps: Option<usize>) -> Self {
-        let step = steps.unwrap_or(360);
-        let step_size = 360.0 / step as f32;
+        let step = steps.unwrap_or(360).max(1);
         Self {
-            hue: 0.0,
-            step_size,
+            hue: 0,
+            step_size: (0x1_0000 / step) as u16,
         }
     }
 
     pub fn new_default() -> Self { Self::new(None) }
 }
 
+impl<const N: usize> PixelStream for Cycle<N> {
+    fn advance(&mut self, _dt: u32) {
+        self.hue = self.hue.wrapping_add(self.step_size);
+    }
+
+    #[cfg(not(feature = "int-math"))]
+    fn pixel<P: Pixel>(&self, _index: usize) -> P {
+        let hue = self.hue as f32 * (360.0 / 65536.0);
+        P::from_hsv(palette::Hsv::new(hue, 1.0, 1.0))
+    }
+
+    #[cfg(feature = "int-math")]
+    fn pixel<P: Pixel>(&self, _index: usize) -> P {
+        use crate::math8::{hsv2rgb_rainbow, Hsv8};
+        P::from_rgb8(hsv2rgb_rainbow(Hsv8::new((self.hue >> 8) as u8, 255, 255)))
+    }
+
+    fn render<P: Pixel>(&self, buf: &mut [P]) -> usize {
+        let px = self.pixel(0);
+        let len = core::cmp::min(N, buf.len());
+        for slot in buf.iter_mut().take(len) { *slot = px; }
+        len
+    }
+}
+
+impl<const N: usize> Seek for Cycle<N> {
+    fn seek(&mut self, step: u64, _step_ticks: u32, _seed: u64) {
+        self.hue = (step as u16).wrapping_mul(self.step_size);
+    }
+}
+
 impl<const N: usize> EffectIterator for Cycle<N> {
     fn name(&self) -> &'static str {
         "Cycle"
     }
 
-    fn next_line(&mut self, buf: &mut [RGB8], _dt: u32) -> Option<usize> {
-        self.hue += self.step_size;
-        if self.hue >= 360.0 {
-            self.hue -= 360.0;
-        }
-        let hsv = Hsv::new(self.hue, 1.0, 1.0);
-        let srgb8: Srgb<u8> = Srgb::from_color(hsv).into_format();
-        let px = RGB8 {
-            r: srgb8.red,
-            g: srgb8.green,
-            b: srgb8.blue,
-        };
-        let len = core::cmp::min(N, buf.len());
-        for slot in buf.iter_mut().take(len) { *slot = px; }
-        Some(len)
+    fn next_line(&mut self, buf: &mut [RGB8], dt: u32) -> Option<usize> {
+        self.next_line_as(buf, dt)
     }
 
     fn pixel_count(&self) -> usize {
         N
     }
 }
+
+impl<const N: usize> RenderAs for Cycle<N> {
+    fn next_line_as<P: Pixel>(&mut self, buf: &mut [P], dt: u32) -> Option<usize> {
+        self.advance(dt);
+        Some(self.render(buf))
+    }
+}
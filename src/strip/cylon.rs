@@ -1,111 +1,308 @@
-use crate::{strip::EffectIterator, RGB8};
+use crate::{
+    math8::{lerp8by8, qadd8, quarter_sine, scale8},
+    strip::{pace::Pace, EffectIterator, PixelStream, RenderAs, Seek},
+    Pixel, RGB8,
+};
 use palette::{FromColor, Hsv, Srgb};
 
-#[derive(Debug, PartialEq)]
-enum Direction {
-    Forward,
-    Backward,
+/// Most eyes a [Cylon] can show.
+const MAX_EYES: usize = 8;
+/// Sub-steps per pixel of movement, for smooth motion at any sweep period.
+const SUBSTEPS: u32 = 256;
+/// One whole back and forth cycle, in 1/65536ths.
+const CYCLE: u32 = 1 << 16;
+
+/// How several [Cylon] eyes move relative to each other.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum CylonEyes {
+    /// Pairs of eyes mirror each other, meeting in the middle.
+    #[default]
+    Opposed,
+    /// Eyes follow each other in a line, spread over half a sweep.
+    Chasing,
 }
 
-impl Direction {
-    fn next(&mut self) {
-        match self {
-            Direction::Forward => *self = Direction::Backward,
-            _ => *self = Direction::Forward,
-        }
-    }
+/// Shape of the trail behind a [Cylon] eye.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum CylonTrail {
+    /// Brightness drops by `fade` per pixel.
+    #[default]
+    Linear,
+    /// Like [CylonTrail::Linear] but squared, so it falls away quickly then lingers.
+    Quadratic,
+    /// Each pixel keeps `1 - fade` of the one in front, like a glowing lamp cooling.
+    Exponential,
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+struct Eye {
+    left: i32, /* in 1/256ths of a pixel */
+    forward: bool,
+    speed: u32, /* relative to full speed, in 1/65536ths */
+    second: bool,
 }
 
+/// The Cylon / Larson scanner: eyes sweep back and forth trailing a fading glow.
+///
+/// By default the eye moves one pixel per frame at a constant speed. [Cylon::set_sweep_ticks]
+/// sets the time for one sweep instead, with the eye drawn at sub-pixel positions, and
+/// [Cylon::set_eased] slows it at the ends like the original KITT scanner.
+///
+/// # Arguments
+///
+/// - `N` - The number of LEDs in the strip.
+/// - `colour` - The eye colour.
+/// - `size` - The eye width in pixels.
+/// - `fade` - How quickly the trail fades per pixel, see [Cylon::set_trail].
 pub struct Cylon<const N: usize> {
+    #[cfg(not(feature = "int-math"))]
     colour: Hsv,
-    direction: Direction,
-    start: usize,
+    #[cfg(not(feature = "int-math"))]
+    second: Option<Hsv>,
+    #[cfg(feature = "int-math")]
+    peak: crate::RGB8,
+    #[cfg(feature = "int-math")]
+    second: Option<crate::RGB8>,
+    phase: u32,
     size: usize,
-    fade: f32,
+    fade: u8,
+    trail_start: u8,
+    curve: CylonTrail,
+    count: usize,
+    arrangement: CylonEyes,
+    eased: bool,
+    eyes: [Eye; MAX_EYES],
+    pace: Pace,
 }
 
 impl<const N: usize> Cylon<N> {
     const DEFAULT_SIZE: usize = 4;
     const DEFAULT_FADE: f32 = 0.2;
+    const DEFAULT_TRAIL_START: f32 = 0.8;
+    /// Slowest the trail is scaled to near the ends of an eased sweep.
+    const MIN_SPEED: u32 = CYCLE / 16;
 
     pub fn new(colour: Srgb<u8>, size: Option<usize>, fade: Option<f32>) -> Self {
         let size = size.unwrap_or(Self::DEFAULT_SIZE).min(N).max(1);
-        Self {
+        let fade = fade.unwrap_or(Self::DEFAULT_FADE);
+        let mut me = Self {
+            #[cfg(not(feature = "int-math"))]
             colour: Hsv::from_color(colour.into_format()),
-            start: size - 1,
-            direction: Direction::Forward,
+            #[cfg(not(feature = "int-math"))]
+            second: None,
+            #[cfg(feature = "int-math")]
+            peak: Self::peak(colour),
+            #[cfg(feature = "int-math")]
+            second: None,
+            phase: 0,
             size,
-            fade: fade.unwrap_or(Self::DEFAULT_FADE),
-        }
+            fade: Self::to_u8(fade),
+            trail_start: Self::to_u8(Self::DEFAULT_TRAIL_START),
+            curve: CylonTrail::default(),
+            count: 1,
+            arrangement: CylonEyes::default(),
+            eased: false,
+            eyes: [Eye::default(); MAX_EYES],
+            pace: Pace::default(),
+        };
+        me.update_eyes();
+        me
     }
 
     pub fn red(size: Option<usize>, fade: Option<f32>) -> Self {
         Self::new(Srgb::<u8>::new(255, 0, 0), size, fade)
     }
-}
 
-impl<const N: usize> EffectIterator for Cylon<N> {
-    fn name(&self) -> &'static str {
-        "Cylon"
+    fn to_u8(fraction: f32) -> u8 {
+        (fraction.clamp(0.0, 1.0) * 255.0) as u8
     }
 
-    fn next_line(&mut self, buf: &mut [RGB8], _dt: u32) -> Option<usize> {
-        let len = core::cmp::min(N, buf.len());
-        // advance position
-        match self.direction {
-            Direction::Forward => {
-                if self.start == N - 1 {
-                    self.direction.next();
-                } else {
-                    self.start += 1;
-                }
-            }
-            Direction::Backward => {
-                if self.start == self.size - 1 {
-                    self.direction.next();
-                } else {
-                    self.start -= 1;
-                }
-            }
+    #[cfg(feature = "int-math")]
+    fn peak(colour: Srgb<u8>) -> crate::RGB8 {
+        let hsv = Hsv::from_color(colour.into_format::<f32>());
+        crate::utils::hsv_to_rgb8_pixel(Hsv::new(hsv.hue, hsv.saturation, 1.0))
+    }
+
+    /// Show `count` eyes (at most 8) arranged as `arrangement`.
+    pub fn set_eyes(&mut self, count: usize, arrangement: CylonEyes) {
+        self.count = count.clamp(1, MAX_EYES);
+        self.arrangement = arrangement;
+        self.update_eyes();
+    }
+
+    /// Slow down towards the ends of each sweep, off by default.
+    pub fn set_eased(&mut self, eased: bool) {
+        self.eased = eased;
+        self.update_eyes();
+    }
+
+    /// Time for one sweep from end to end, or None (the default) to move one pixel per frame.
+    pub fn set_sweep_ticks(&mut self, ticks: Option<u32>) {
+        self.pace.set_ticks(ticks.map(|t| t.saturating_mul(2)));
+    }
+
+    /// Trail brightness next to the eye (0.8 by default) and how it fades away.
+    pub fn set_trail(&mut self, start: f32, curve: CylonTrail) {
+        self.trail_start = Self::to_u8(start);
+        self.curve = curve;
+    }
+
+    /// Colour of every other eye, or of the return sweep when there is only one eye.
+    pub fn set_second_colour(&mut self, colour: Option<Srgb<u8>>) {
+        #[cfg(not(feature = "int-math"))]
+        {
+            self.second = colour.map(|c| Hsv::from_color(c.into_format()));
         }
+        #[cfg(feature = "int-math")]
+        {
+            self.second = colour.map(Self::peak);
+        }
+    }
 
-        // render
-        for (i, slot) in buf.iter_mut().enumerate().take(len) {
-            let mut brightness = 0.0f32;
-            // head segment [start - size + 1 ..= start]
-            if self.start + 1 >= self.size {
-                let head_start = self.start + 1 - self.size;
-                if i >= head_start && i <= self.start {
-                    brightness = 1.0;
-                }
-            }
-            // trail
-            match self.direction {
-                Direction::Forward => {
-                    if self.start + 1 > self.size {
-                        let tail_end = self.start + 1 - self.size; // inclusive index behind head
-                        if i <= tail_end {
-                            let dist = (tail_end - i) as f32;
-                            brightness += (0.8 - self.fade * dist).max(0.0);
-                        }
-                    }
-                }
-                Direction::Backward => {
-                    if i >= self.start {
-                        let dist = (i - self.start) as f32;
-                        brightness += (0.8 - self.fade * dist).max(0.0);
-                    }
+    /// Pixels the eye travels between the ends.
+    fn span(&self) -> u32 {
+        N.saturating_sub(self.size) as u32
+    }
+
+    /// Length of a whole back and forth cycle in sub-steps.
+    fn cycle_len(&self) -> u32 {
+        2 * self.span().max(1) * SUBSTEPS
+    }
+
+    fn update_eyes(&mut self) {
+        let fraction = (self.phase as u64 * CYCLE as u64 / self.cycle_len() as u64) as u32;
+        let span = self.span() as u64;
+        for k in 0..self.count {
+            let offset = match self.arrangement {
+                CylonEyes::Opposed => (k as u32 % 2) * CYCLE / 2 + (k as u32 / 2) * CYCLE / self.count as u32,
+                CylonEyes::Chasing => CYCLE - k as u32 * CYCLE / (4 * self.count as u32),
+            };
+            let f = (fraction + offset) % CYCLE;
+            let forward = f < CYCLE / 2;
+            let travelled = if forward { 2 * f } else { 2 * (CYCLE - f) };
+            let (along, speed) = if self.eased {
+                // sin² eases in and out, its slope is sin(2θ) = 2·sin·cos
+                let (s, c) = (quarter_sine(travelled), quarter_sine(CYCLE - travelled));
+                let along = (s as u64 * s as u64 / CYCLE as u64) as u32;
+                let speed = (2 * s as u64 * c as u64 / CYCLE as u64) as u32;
+                (along, speed.max(Self::MIN_SPEED))
+            } else {
+                (travelled, CYCLE)
+            };
+            self.eyes[k] = Eye {
+                left: (along as u64 * span * SUBSTEPS as u64 / CYCLE as u64) as i32,
+                forward,
+                speed,
+                second: if self.count > 1 { k % 2 == 1 } else { !forward },
+            };
+        }
+    }
+
+    /// Trail brightness `distance` (in 1/256ths of a pixel) behind the eye.
+    fn trail(&self, distance: u32) -> u8 {
+        let linear = 255u32.saturating_sub(self.fade as u32 * distance / SUBSTEPS) as u8;
+        let level = match self.curve {
+            CylonTrail::Linear => linear,
+            CylonTrail::Quadratic => scale8(linear, linear),
+            CylonTrail::Exponential => {
+                let keep = 255 - self.fade;
+                let mut level = 255u8;
+                for _ in 0..(distance / SUBSTEPS).min(64) {
+                    level = scale8(level, keep);
                 }
+                lerp8by8(level, scale8(level, keep), (distance % SUBSTEPS) as u8)
             }
-            let mut hsv = self.colour;
-            hsv.value = brightness.min(1.0);
-            let srgb8: Srgb<u8> = Srgb::from_color(hsv).into_format();
-            *slot = RGB8 { r: srgb8.red, g: srgb8.green, b: srgb8.blue };
-        }
-        Some(len)
+        };
+        scale8(level, self.trail_start)
+    }
+
+    /// Brightness of pixel `i` from `eye`.
+    fn brightness(&self, eye: &Eye, i: usize) -> u8 {
+        let low = i as i32 * SUBSTEPS as i32;
+        let high = low + SUBSTEPS as i32;
+        let right = eye.left + (self.size as u32 * SUBSTEPS) as i32;
+        let cover = (high.min(right) - low.max(eye.left)).clamp(0, 255) as u8;
+        // the trail lengthens with speed, so it shrinks as an eased eye slows at the ends
+        let behind = if eye.forward { eye.left - low } else { high - right };
+        let trail = if behind > 0 {
+            let distance = behind as u64 * CYCLE as u64 / eye.speed as u64;
+            self.trail(distance.min(u32::MAX as u64) as u32)
+        } else {
+            0
+        };
+        qadd8(cover, trail)
+    }
+
+    /// Brightest eye at pixel `i`: its brightness and whether it uses the second colour.
+    fn brightest(&self, i: usize) -> (u8, bool) {
+        self.eyes[..self.count]
+            .iter()
+            .map(|eye| (self.brightness(eye, i), eye.second))
+            .max_by_key(|(b, _)| *b)
+            .unwrap_or((0, false))
+    }
+}
+
+impl<const N: usize> PixelStream for Cylon<N> {
+    fn advance(&mut self, dt: u32) {
+        let len = self.cycle_len();
+        // one pixel per frame, or sub-steps paced to the sweep period
+        let steps = if self.pace.is_paced() {
+            self.pace.steps(dt, len as usize) as u32
+        } else {
+            SUBSTEPS
+        };
+        self.phase = (self.phase + steps % len) % len;
+        self.update_eyes();
+    }
+
+    #[cfg(not(feature = "int-math"))]
+    fn pixel<P: Pixel>(&self, i: usize) -> P {
+        let (brightness, second) = self.brightest(i);
+        let mut hsv = if second { self.second.unwrap_or(self.colour) } else { self.colour };
+        hsv.value = brightness as f32 / 255.0;
+        P::from_hsv(hsv)
+    }
+
+    #[cfg(feature = "int-math")]
+    fn pixel<P: Pixel>(&self, i: usize) -> P {
+        use crate::math8::scale8_rgb;
+        let (brightness, second) = self.brightest(i);
+        let peak = if second { self.second.unwrap_or(self.peak) } else { self.peak };
+        P::from_rgb8(scale8_rgb(peak, brightness))
+    }
+}
+
+impl<const N: usize> Seek for Cylon<N> {
+    fn seek(&mut self, step: u64, step_ticks: u32, _seed: u64) {
+        let len = self.cycle_len() as u64;
+        let steps = if self.pace.is_paced() {
+            self.pace.seek(step, step_ticks, len as usize)
+        } else {
+            step * SUBSTEPS as u64
+        };
+        self.phase = (steps % len) as u32;
+        self.update_eyes();
+    }
+}
+
+impl<const N: usize> EffectIterator for Cylon<N> {
+    fn name(&self) -> &'static str {
+        "Cylon"
+    }
+
+    fn next_line(&mut self, buf: &mut [RGB8], dt: u32) -> Option<usize> {
+        self.next_line_as(buf, dt)
     }
 
     fn pixel_count(&self) -> usize {
         N
     }
 }
+
+impl<const N: usize> RenderAs for Cylon<N> {
+    fn next_line_as<P: Pixel>(&mut self, buf: &mut [P], dt: u32) -> Option<usize> {
+        self.advance(dt);
+        Some(self.render(buf))
+    }
+}
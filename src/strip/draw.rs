@@ -0,0 +1,29 @@
+use crate::Pixel;
+use palette::Srgb;
+
+/// Draw `colour` scaled by `coverage` over pixel `index`, keeping the brighter channels.
+pub(crate) fn blend<P: Pixel>(buf: &mut [P], index: i32, colour: Srgb, coverage: f32) {
+    if index < 0 || coverage <= 0.0 {
+        return;
+    }
+    if let Some(slot) = buf.get_mut(index as usize) {
+        let under = slot.to_srgb();
+        let over = colour * coverage.min(1.0);
+        *slot = P::from_srgb(Srgb::new(
+            under.red.max(over.red),
+            under.green.max(over.green),
+            under.blue.max(over.blue),
+        ));
+    }
+}
+
+/// Anti-aliased span from `low` to `high` (in pixels): each pixel is lit by how much of it
+/// the span covers.
+pub(crate) fn span<P: Pixel>(buf: &mut [P], low: f32, high: f32, colour: Srgb) {
+    let first = libm::floorf(low).max(-1.0) as i32;
+    let last = libm::floorf(high).min(buf.len() as f32) as i32;
+    for i in first..=last {
+        let coverage = (high.min(i as f32 + 1.0) - low.max(i as f32)).max(0.0);
+        blend(buf, i, colour, coverage);
+    }
+}
@@ -1,9 +1,13 @@
-use crate::RGB8;
+use crate::{Pixel, RGB8};
 
 #[cfg(feature = "std")]
 extern crate std;
 
 /// Core, no_alloc effect interface: render into a caller-provided buffer.
+///
+/// The trait is object safe, so effects can be switched at runtime through
+/// `Box<dyn EffectIterator>` or `&mut dyn EffectIterator`. Rendering into other [Pixel] types
+/// is provided by [RenderAs].
 pub trait EffectIterator {
     fn name(&self) -> &'static str;
 
@@ -24,3 +28,10 @@ pub trait EffectIterator {
         Some(buf)
     }
 }
+
+/// Effects that can render into any [Pixel] type, e.g. [RGB8], [crate::RGB16] or
+/// [crate::Srgb]. All the effects in this crate implement it.
+pub trait RenderAs: EffectIterator {
+    /// Same as [EffectIterator::next_line] into a buffer of any [Pixel] type.
+    fn next_line_as<P: Pixel>(&mut self, buf: &mut [P], dt_ticks: u32) -> Option<usize>;
+}
@@ -1,77 +1,243 @@
-use crate::{strip::EffectIterator, RGB8};
+use crate::{
+    math8::lerp8by8,
+    rng::FastRng,
+    strip::{pace::Pace, EffectIterator, Fill, RenderAs},
+    Pixel, RGB8,
+};
 use rand_core::RngCore;
 
-pub struct Fire<const N: usize, R: RngCore> {
+/// How heat maps to colour.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FirePalette {
+    /// The classic Fire2012 black-body ramp: black, red, yellow, white.
+    Heat,
+    /// Gas flame: black, blue, cyan, white.
+    Blue,
+    /// Chemical flame: black, green, lime, white.
+    Green,
+    /// Gradient through four colours, from no heat to the hottest.
+    Custom([RGB8; 4]),
+}
+
+impl FirePalette {
+    const BLUE: [RGB8; 4] = [
+        RGB8 { r: 0, g: 0, b: 0 },
+        RGB8 { r: 0, g: 0, b: 255 },
+        RGB8 { r: 0, g: 170, b: 255 },
+        RGB8 { r: 255, g: 255, b: 255 },
+    ];
+    const GREEN: [RGB8; 4] = [
+        RGB8 { r: 0, g: 0, b: 0 },
+        RGB8 { r: 0, g: 160, b: 0 },
+        RGB8 { r: 160, g: 255, b: 0 },
+        RGB8 { r: 255, g: 255, b: 255 },
+    ];
+
+    fn colour(&self, heat: u8) -> RGB8 {
+        match self {
+            FirePalette::Heat => heat_to_colour(heat),
+            FirePalette::Blue => gradient(&Self::BLUE, heat),
+            FirePalette::Green => gradient(&Self::GREEN, heat),
+            FirePalette::Custom(stops) => gradient(stops, heat),
+        }
+    }
+}
+
+fn heat_to_colour(val: u8) -> RGB8 {
+    let (r, g, b) = if val >= 0x85 {
+        let heat_ramp = 3u8.saturating_mul(val - 0x85);
+        (255, 255, heat_ramp)
+    } else if val >= 0x40 {
+        let heat_ramp = 3u8.saturating_mul(val - 0x40);
+        (255, heat_ramp, 0)
+    } else {
+        let heat_ramp = 3u8.saturating_mul(val);
+        (heat_ramp, 0, 0)
+    };
+    RGB8 { r, g, b }
+}
+
+/// Linear gradient through four evenly spaced stops.
+fn gradient(stops: &[RGB8; 4], heat: u8) -> RGB8 {
+    // three segments of 85 steps each
+    let segment = (heat / 85).min(2) as usize;
+    let frac = ((heat - segment as u8 * 85) as u16 * 3).min(255) as u8;
+    let (a, b) = (stops[segment], stops[segment + 1]);
+    RGB8 {
+        r: lerp8by8(a.r, b.r, frac),
+        g: lerp8by8(a.g, b.g, frac),
+        b: lerp8by8(a.b, b.b, frac),
+    }
+}
+
+/// Fire2012: heat rises from sparks at the base, diffuses upwards and cools.
+///
+/// The base is at index 0 by default; [Fire::set_fill] burns from the far end, from the
+/// centre out or from both ends in. Each spark zone is a separate base along the flame, see
+/// [Fire::set_spark_zones]. With [Fire::set_step_ticks] the simulation runs at a fixed rate
+/// whatever the frame rate.
+pub struct Fire<const N: usize, R: RngCore = FastRng> {
+    cooling_setting: u8,
+    base_cooling: u8,
+    base_sparking: u8,
     cooling: u8,
     sparking: u8,
+    intensity: f32,
     heat: [u8; N],
+    palette: FirePalette,
+    fill: Fill,
+    zones: usize,
+    pace: Pace,
     rng: R,
 }
 
 impl<const N: usize, R: RngCore> Fire<N, R> {
     const DEFAULT_COOLING: u8 = 40;
     const DEFAULT_SPARKING: u8 = 120;
+    /// Most simulation steps run in one frame; steps beyond this are dropped.
+    const MAX_STEPS_PER_FRAME: usize = 4;
     pub fn new(rng: R, cooling: Option<u8>, sparking: Option<u8>) -> Self {
-        let base_cool = cooling.unwrap_or(Self::DEFAULT_COOLING) as f32;
-        let cooling = (((base_cool * 10.0) / N as f32) + 2.0) as u8;
-        Self {
-            cooling,
-            sparking: sparking.unwrap_or(Self::DEFAULT_SPARKING),
+        let sparking = sparking.unwrap_or(Self::DEFAULT_SPARKING);
+        let mut me = Self {
+            cooling_setting: cooling.unwrap_or(Self::DEFAULT_COOLING),
+            base_cooling: 0,
+            base_sparking: sparking,
+            cooling: 0,
+            sparking,
+            intensity: 1.0,
             heat: [0; N],
+            palette: FirePalette::Heat,
+            fill: Fill::Forward,
+            zones: 1,
+            pace: Pace::default(),
             rng,
-        }
+        };
+        me.update_cooling();
+        me
     }
 
     pub fn default(rng: R) -> Self { Self::new(rng, None, None) }
 
-    fn heat_to_colour(val: u8) -> RGB8 {
-        let (r, g, b) = if val >= 0x85 {
-            let heat_ramp = 3u8.saturating_mul(val - 0x85);
-            (255, 255, heat_ramp)
-        } else if val >= 0x40 {
-            let heat_ramp = 3u8.saturating_mul(val - 0x40);
-            (255, heat_ramp, 0)
-        } else {
-            let heat_ramp = 3u8.saturating_mul(val);
-            (heat_ramp, 0, 0)
-        };
-        RGB8 { r, g, b }
+    /// Number of cells simulated: the whole strip, or half of it when mirrored.
+    fn flame_len(&self) -> usize {
+        match self.fill {
+            Fill::Forward | Fill::Reverse => N,
+            Fill::CentreOut | Fill::EdgesIn => N.div_ceil(2),
+        }
     }
-}
 
-impl<const N: usize, R: RngCore> EffectIterator for Fire<N, R> {
-    fn name(&self) -> &'static str {
-        "Fire"
+    fn update_cooling(&mut self) {
+        // cooling is per cell, so shorter flames need more of it to reach the same height
+        let base_cool = self.cooling_setting as usize;
+        self.base_cooling = ((base_cool * 10) / self.flame_len().max(1) + 2).min(u8::MAX as usize) as u8;
+        self.set_intensity(self.intensity);
+    }
+
+    /// Scale the fire between dying out (`0.0`) and the configured cooling and sparking
+    /// (`1.0`), e.g. from [crate::audio::Analyser::level]. Can be changed every frame.
+    pub fn set_intensity(&mut self, intensity: f32) {
+        let intensity = intensity.clamp(0.0, 1.0);
+        self.intensity = intensity;
+        self.sparking = (self.base_sparking as f32 * intensity) as u8;
+        // a quiet fire also cools faster, up to twice the configured rate
+        self.cooling = (self.base_cooling as f32 * (2.0 - intensity)).min(u8::MAX as f32) as u8;
+    }
+
+    pub fn set_palette(&mut self, palette: FirePalette) {
+        self.palette = palette;
+    }
+
+    /// Where the flame burns from. [Fill::CentreOut] and [Fill::EdgesIn] mirror a flame of
+    /// half the length.
+    pub fn set_fill(&mut self, fill: Fill) {
+        self.fill = fill;
+        self.heat = [0; N];
+        self.update_cooling();
+    }
+
+    /// Number of evenly spaced bases sparking along each flame, 1 by default.
+    pub fn set_spark_zones(&mut self, zones: usize) {
+        self.zones = zones.max(1);
     }
 
-    fn next_line(&mut self, buf: &mut [RGB8], _dt: u32) -> Option<usize> {
+    /// Run one simulation step every `ticks` instead of one per frame. At most 4 steps run per
+    /// frame, so a long gap between frames can't make the next one slow to render.
+    pub fn set_step_ticks(&mut self, ticks: Option<u32>) {
+        self.pace.set_ticks(ticks);
+    }
+
+    fn step(&mut self) {
+        let len = self.flame_len();
+        let heat = &mut self.heat[..len];
         // apply cooling
-        for spark in self.heat.iter_mut() {
+        for spark in heat.iter_mut() {
             let x = (self.rng.next_u32() % self.cooling as u32) as u8;
             *spark = spark.saturating_sub(x);
         }
         // apply heating diffusion
-        for i in (2..N).rev() {
-            self.heat[i] = (self.heat[i - 1]
-                .saturating_add(self.heat[i - 2])
-                .saturating_add(self.heat[i - 2]))
+        for i in (2..len).rev() {
+            heat[i] = (heat[i - 1]
+                .saturating_add(heat[i - 2])
+                .saturating_add(heat[i - 2]))
                 / 3;
         }
-        // generate sparks
-        if ((self.rng.next_u32() % 255) as u8) < self.sparking {
-            let y = (self.rng.next_u32() as usize) % ((N.max(1) / 7) + 1);
-            let add = 160 + (self.rng.next_u32() % 95) as u8; // 160..255
-            self.heat[y] = self.heat[y].saturating_add(add);
+        // generate sparks near the base of each zone
+        let zone_len = len / self.zones;
+        for zone in 0..self.zones {
+            if ((self.rng.next_u32() % 255) as u8) < self.sparking {
+                let y = zone * zone_len + (self.rng.next_u32() as usize) % ((zone_len / 7) + 1);
+                let add = 160 + (self.rng.next_u32() % 95) as u8; // 160..255
+                if let Some(cell) = heat.get_mut(y) {
+                    *cell = cell.saturating_add(add);
+                }
+            }
         }
-        // write colours
-        let len = core::cmp::min(N, buf.len());
-        for (i, slot) in buf.iter_mut().enumerate().take(len) {
-            *slot = Self::heat_to_colour(self.heat[i]);
+    }
+
+    /// Heat cell shown at pixel `i`.
+    fn cell(&self, i: usize) -> usize {
+        let edge = i.min(N - 1 - i);
+        match self.fill {
+            Fill::Forward => i,
+            Fill::Reverse => N - 1 - i,
+            Fill::EdgesIn => edge,
+            Fill::CentreOut => self.flame_len() - 1 - edge,
         }
-        Some(len)
+    }
+}
+
+impl<const N: usize> Fire<N> {
+    /// Same as [Fire::new] using a [FastRng] seeded with `seed`.
+    pub fn seeded(seed: u64, cooling: Option<u8>, sparking: Option<u8>) -> Self {
+        Self::new(FastRng::new(seed), cooling, sparking)
+    }
+}
+
+impl<const N: usize, R: RngCore> EffectIterator for Fire<N, R> {
+    fn name(&self) -> &'static str {
+        "Fire"
+    }
+
+    fn next_line(&mut self, buf: &mut [RGB8], dt: u32) -> Option<usize> {
+        self.next_line_as(buf, dt)
     }
 
     fn pixel_count(&self) -> usize {
         N
     }
 }
+
+impl<const N: usize, R: RngCore> RenderAs for Fire<N, R> {
+    fn next_line_as<P: Pixel>(&mut self, buf: &mut [P], dt: u32) -> Option<usize> {
+        let steps = self.pace.steps(dt, 1).min(Self::MAX_STEPS_PER_FRAME);
+        for _ in 0..steps {
+            self.step();
+        }
+        // write colours
+        let len = core::cmp::min(N, buf.len());
+        for (i, slot) in buf.iter_mut().enumerate().take(len) {
+            *slot = P::from_rgb8(self.palette.colour(self.heat[self.cell(i)]));
+        }
+        Some(len)
+    }
+}
@@ -0,0 +1,275 @@
+use crate::{
+    strip::{EffectIterator, RenderAs},
+    Pixel, RGB8,
+};
+use palette::Srgb;
+
+/// Most transitions remembered for the one second window.
+const MAX_HISTORY: usize = 64;
+
+/// How often a [FlashLimiter] has seen and stopped flashes.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct FlashReport {
+    /// Frames rendered.
+    pub frames: u32,
+    /// Frames where a large part of the strip switched between light and dark (half a flash)
+    /// and was let through.
+    pub transitions: u32,
+    /// Frames where a transition would have gone over the limit and was held back.
+    pub interventions: u32,
+    /// Pixels held at their previous colour, summed over all interventions.
+    pub held_pixels: u32,
+}
+
+/// Last turning point of one pixel, and what it last showed.
+#[derive(Debug, Clone, Copy)]
+struct Track {
+    luma: f32,
+    luma_dir: i8,
+    red: f32,
+    red_dir: i8,
+    red_saturated: bool,
+    shown: Srgb,
+}
+
+impl Track {
+    const NEW: Track = Track {
+        luma: 0.0,
+        luma_dir: 0,
+        red: 0.0,
+        red_dir: 0,
+        red_saturated: false,
+        shown: Srgb::new(0.0, 0.0, 0.0),
+    };
+}
+
+/// Follow a value between turning points. Returns whether it has reversed by at least
+/// `threshold`, and if so the new direction.
+fn turn(reference: f32, direction: i8, value: f32, threshold: f32) -> Option<i8> {
+    let delta = value - reference;
+    let sign = if delta > 0.0 { 1 } else { -1 };
+    (delta.abs() >= threshold && sign != direction).then_some(sign)
+}
+
+/// Move the reference along while the value keeps going the same way.
+fn follow(reference: &mut f32, direction: i8, value: f32) {
+    if (direction > 0 && value > *reference) || (direction < 0 && value < *reference) {
+        *reference = value;
+    }
+}
+
+/// Photosensitive epilepsy safeguard that can wrap any effect.
+///
+/// Every frame is compared with what was shown before, along the lines of the WCAG 2.3.1
+/// general and red flash thresholds. A pixel makes a transition when its relative luminance
+/// reverses by 10% or more (with the darker state below 0.8), or when saturated red changes
+/// by more than 20/320. When at least a quarter of the strip transitions in the same frame
+/// that is half a flash; more than 3 flashes in any second are not let through, and the
+/// transitioning pixels hold their previous colour instead. Ticks are taken as milliseconds.
+///
+/// `N` must be at least the pixel count of the effect; pixels beyond it are not checked.
+///
+/// ```rust
+/// use smart_led_effects::{strip::{EffectIterator, FlashLimiter, Strobe}, RGB8};
+///
+/// let mut strobe = FlashLimiter::<10, _>::new(Strobe::<10>::seeded(1, None, 50, Some(0.5)));
+/// let mut pixels = [RGB8::default(); 10];
+/// for _ in 0..100 {
+///     strobe.next_line(&mut pixels, 10);
+/// }
+/// let report = strobe.report();
+/// assert!(report.interventions > 0);
+/// assert!(report.transitions <= 6);
+/// ```
+pub struct FlashLimiter<const N: usize, E: EffectIterator> {
+    effect: E,
+    tracks: [Track; N],
+    started: bool,
+    max_flashes: f32,
+    area: f32,
+    time_ms: u64,
+    history: [u64; MAX_HISTORY],
+    history_len: usize,
+    report: FlashReport,
+}
+
+impl<const N: usize, E: EffectIterator> FlashLimiter<N, E> {
+    const DEFAULT_MAX_FLASHES: f32 = 3.0;
+    const DEFAULT_AREA: f32 = 0.25;
+    const WINDOW_MS: u64 = 1000;
+    const LUMA_THRESHOLD: f32 = 0.1;
+    const DARK_LIMIT: f32 = 0.8;
+    const RED_THRESHOLD: f32 = 20.0 / 320.0;
+    const RED_SATURATION: f32 = 0.8;
+
+    pub fn new(effect: E) -> Self {
+        Self {
+            effect,
+            tracks: [Track::NEW; N],
+            started: false,
+            max_flashes: Self::DEFAULT_MAX_FLASHES,
+            area: Self::DEFAULT_AREA,
+            time_ms: 0,
+            history: [0; MAX_HISTORY],
+            history_len: 0,
+            report: FlashReport::default(),
+        }
+    }
+
+    /// Flashes allowed in any one second, 3 by default.
+    pub fn set_max_flashes(&mut self, flashes: f32) {
+        self.max_flashes = flashes.max(0.0);
+    }
+
+    /// Fraction of the strip that has to change together to count as a flash, 0.25 by
+    /// default. Smaller is stricter.
+    pub fn set_area(&mut self, area: f32) {
+        self.area = area.clamp(0.0, 1.0);
+    }
+
+    pub fn report(&self) -> FlashReport {
+        self.report
+    }
+
+    pub fn reset_report(&mut self) {
+        self.report = FlashReport::default();
+    }
+
+    pub fn inner(&self) -> &E {
+        &self.effect
+    }
+
+    pub fn inner_mut(&mut self) -> &mut E {
+        &mut self.effect
+    }
+
+    pub fn into_inner(self) -> E {
+        self.effect
+    }
+
+    /// Relative luminance, and the red flash measure with whether it is saturated red.
+    fn measure(colour: Srgb) -> (f32, f32, bool) {
+        let linear = colour.into_linear();
+        let luma = 0.2126 * linear.red + 0.7152 * linear.green + 0.0722 * linear.blue;
+        let sum = colour.red + colour.green + colour.blue;
+        let red = (colour.red - colour.green - colour.blue).max(0.0);
+        (luma, red, sum > 0.0 && colour.red / sum >= Self::RED_SATURATION)
+    }
+
+    /// Whether pixel `i` showing `colour` would be a transition.
+    fn transitions(&self, i: usize, colour: Srgb) -> bool {
+        let track = &self.tracks[i];
+        let (luma, red, saturated) = Self::measure(colour);
+        let dark = luma.min(track.luma) < Self::DARK_LIMIT;
+        let luma_turn = dark && turn(track.luma, track.luma_dir, luma, Self::LUMA_THRESHOLD).is_some();
+        let red_turn = (saturated || track.red_saturated)
+            && turn(track.red, track.red_dir, red, Self::RED_THRESHOLD).is_some();
+        luma_turn || red_turn
+    }
+
+    /// Record that pixel `i` now shows `colour`.
+    fn show(&mut self, i: usize, colour: Srgb) {
+        let (luma, red, saturated) = Self::measure(colour);
+        let track = &mut self.tracks[i];
+        if !self.started {
+            *track = Track { luma, luma_dir: 0, red, red_dir: 0, red_saturated: saturated, shown: colour };
+            return;
+        }
+        match turn(track.luma, track.luma_dir, luma, Self::LUMA_THRESHOLD) {
+            Some(direction) => {
+                track.luma = luma;
+                track.luma_dir = direction;
+            }
+            None => follow(&mut track.luma, track.luma_dir, luma),
+        }
+        match turn(track.red, track.red_dir, red, Self::RED_THRESHOLD) {
+            Some(direction) => {
+                track.red = red;
+                track.red_dir = direction;
+                track.red_saturated = saturated;
+            }
+            None => follow(&mut track.red, track.red_dir, red),
+        }
+        track.shown = colour;
+    }
+
+    /// Forget transitions older than the window.
+    fn expire(&mut self) {
+        let cutoff = self.time_ms.saturating_sub(Self::WINDOW_MS);
+        let mut kept = 0;
+        for i in 0..self.history_len {
+            if self.history[i] > cutoff {
+                self.history[kept] = self.history[i];
+                kept += 1;
+            }
+        }
+        self.history_len = kept;
+    }
+
+    fn record(&mut self) {
+        if self.history_len == MAX_HISTORY {
+            self.history.copy_within(1.., 0);
+            self.history_len -= 1;
+        }
+        self.history[self.history_len] = self.time_ms;
+        self.history_len += 1;
+    }
+
+    /// Check the frame the effect just rendered into `buf`, holding back pixels that would
+    /// flash too often.
+    fn limit<P: Pixel>(&mut self, buf: &mut [P], len: usize, dt_ticks: u32) {
+        let checked = len.min(N).min(buf.len());
+        self.time_ms += dt_ticks as u64;
+        self.report.frames = self.report.frames.saturating_add(1);
+        self.expire();
+
+        let changing = (0..checked).filter(|&i| self.transitions(i, buf[i].to_srgb())).count();
+        let flash = self.started && changing > 0 && changing as f32 >= self.area * checked as f32;
+        // two transitions make a flash
+        let allowed = (2.0 * self.max_flashes) as usize;
+        if flash && self.history_len >= allowed {
+            for (i, slot) in buf.iter_mut().enumerate().take(checked) {
+                if self.transitions(i, slot.to_srgb()) {
+                    *slot = P::from_srgb(self.tracks[i].shown);
+                } else {
+                    self.show(i, slot.to_srgb());
+                }
+            }
+            self.report.interventions = self.report.interventions.saturating_add(1);
+            self.report.held_pixels = self.report.held_pixels.saturating_add(changing as u32);
+        } else {
+            for (i, slot) in buf.iter().enumerate().take(checked) {
+                self.show(i, slot.to_srgb());
+            }
+            if flash {
+                self.record();
+                self.report.transitions = self.report.transitions.saturating_add(1);
+            }
+        }
+        self.started = true;
+    }
+}
+
+impl<const N: usize, E: EffectIterator> EffectIterator for FlashLimiter<N, E> {
+    fn name(&self) -> &'static str {
+        self.effect.name()
+    }
+
+    fn next_line(&mut self, buf: &mut [RGB8], dt_ticks: u32) -> Option<usize> {
+        let len = self.effect.next_line(buf, dt_ticks)?;
+        self.limit(buf, len, dt_ticks);
+        Some(len)
+    }
+
+    fn pixel_count(&self) -> usize {
+        self.effect.pixel_count()
+    }
+}
+
+impl<const N: usize, E: RenderAs> RenderAs for FlashLimiter<N, E> {
+    fn next_line_as<P: Pixel>(&mut self, buf: &mut [P], dt_ticks: u32) -> Option<usize> {
+        let len = self.effect.next_line_as(buf, dt_ticks)?;
+        self.limit(buf, len, dt_ticks);
+        Some(len)
+    }
+}
@@ -1,76 +1,217 @@
-use crate::{strip::EffectIterator, RGB8};
-use palette::Srgb;
+use crate::{
+    rng::{FastRng, RandomExt},
+    strip::{draw::span, EffectIterator, RenderAs},
+    Pixel, RGB8,
+};
+use core::ops::Range;
+use palette::{FromColor, Hsv, Srgb};
 use rand_core::RngCore;
 
-pub struct Meteor<const N: usize, R: RngCore> {
+/// Which way meteors travel along the strip.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum MeteorDirection {
+    /// From index 0 towards the far end.
+    #[default]
+    Forward,
+    Reverse,
+    /// Each meteor picks a direction at random.
+    Both,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Rock {
+    head: f32,     /* leading edge, in pixels */
+    velocity: f32, /* pixels per a second */
     colour: Srgb,
-    size: usize,
-    position: usize,
+    alive: bool,
+    wait: f32, /* seconds until the next launch */
+}
+
+/// Meteor rain: meteors fly along the strip leaving trails of randomly fading debris.
+///
+/// Each of the `M` meteors relaunches after a random pause with a random speed, in pixels per
+/// second with `dt_ticks` in milliseconds. Heads are drawn anti-aliased at fractional positions.
+///
+/// # Arguments
+///
+/// - `N` - The number of LEDs in the strip.
+/// - `R` - The random number source type, [FastRng] by default.
+/// - `M` - The number of meteors, 1 by default.
+/// - `rng` - The random number source.
+/// - `colour` - Colour of every meteor, or None for random colours, see [Meteor::set_palette]
+///   and [Meteor::set_hues].
+/// - `size` - Length of the solid head in pixels.
+/// - `fade` - Fraction of brightness a trail pixel loses, half of the time, every 20 ticks.
+pub struct Meteor<const N: usize, R: RngCore = FastRng, const M: usize = 1> {
+    rocks: [Rock; M],
+    colour: Option<Srgb>,
+    palette: Option<&'static [Srgb]>,
+    hues: Range<f32>,
+    size: f32,
     fade: f32,
+    speeds: Range<f32>,
+    spawn: Range<f32>,
+    direction: MeteorDirection,
     current: [Srgb; N],
-    random_colour: bool,
     rng: R,
 }
 
-impl<const N: usize, R: RngCore> Meteor<N, R> {
+impl<const N: usize, R: RngCore, const M: usize> Meteor<N, R, M> {
     const DEFAULT_SIZE: usize = 4;
     const DEFAULT_FADE: f32 = 0.3;
-    const DEFAULT_COLOUR: Srgb<u8> = Srgb::<u8>::new(255, 255, 255);
+    const DEFAULT_SPEEDS: Range<f32> = 30.0..60.0;
+    const DEFAULT_SPAWN: Range<f32> = 0.0..1.5;
+    /// Ticks over which `fade` applies.
+    const FADE_TICKS: f32 = 20.0;
 
     pub fn new(rng: R, colour: Option<Srgb<u8>>, size: Option<usize>, fade: Option<f32>) -> Self {
-        Self {
-            colour: colour.unwrap_or(Self::DEFAULT_COLOUR).into_format(),
-            size: size.unwrap_or(Self::DEFAULT_SIZE).min(N).max(1),
-            position: 0,
+        let mut me = Self {
+            rocks: [Rock {
+                head: 0.0,
+                velocity: 0.0,
+                colour: Srgb::new(1.0, 1.0, 1.0),
+                alive: false,
+                wait: 0.0,
+            }; M],
+            colour: colour.map(|c| c.into_format()),
+            palette: None,
+            hues: 0.0..360.0,
+            size: size.unwrap_or(Self::DEFAULT_SIZE).min(N).max(1) as f32,
             fade: fade.unwrap_or(Self::DEFAULT_FADE),
+            speeds: Self::DEFAULT_SPEEDS,
+            spawn: Self::DEFAULT_SPAWN,
+            direction: MeteorDirection::default(),
             current: [Srgb::new(0.0, 0.0, 0.0); N],
-            random_colour: colour.is_none(),
             rng,
+        };
+        // the first meteor goes straight away, the others are staggered
+        for i in 1..M {
+            me.rocks[i].wait = me.rng.range_f32(me.spawn.clone());
         }
+        me
     }
-}
 
-impl<const N: usize, R: RngCore> EffectIterator for Meteor<N, R> {
-    fn name(&self) -> &'static str {
-        "Meteor"
+    /// Launch speeds in pixels per second, 30 to 60 by default.
+    pub fn set_speeds(&mut self, speeds: Range<f32>) {
+        self.speeds = speeds;
+    }
+
+    /// Pause before a meteor relaunches, in ticks, 0 to 1500 by default.
+    pub fn set_spawn_ticks(&mut self, ticks: Range<u32>) {
+        self.spawn = ticks.start as f32 / 1000.0..ticks.end as f32 / 1000.0;
+    }
+
+    pub fn set_direction(&mut self, direction: MeteorDirection) {
+        self.direction = direction;
+    }
+
+    /// Colour of every meteor, or None for random colours.
+    pub fn set_colour(&mut self, colour: Option<Srgb>) {
+        self.colour = colour;
+    }
+
+    /// Random colours are picked from `palette`, or with a random hue when None.
+    pub fn set_palette(&mut self, palette: Option<&'static [Srgb]>) {
+        self.palette = palette.filter(|p| !p.is_empty());
+    }
+
+    /// Hues in degrees for random colours without a palette, the whole wheel by default.
+    pub fn set_hues(&mut self, hues: Range<f32>) {
+        self.hues = hues;
+    }
+
+    fn random_colour(&mut self) -> Srgb {
+        if let Some(colour) = self.colour {
+            return colour;
+        }
+        match self.palette {
+            Some(palette) => palette[self.rng.index(palette.len())],
+            None => Srgb::from_color(Hsv::new(self.rng.range_f32(self.hues.clone()), 1.0, 1.0)),
+        }
+    }
+
+    fn launch(&mut self, index: usize) {
+        let forward = match self.direction {
+            MeteorDirection::Forward => true,
+            MeteorDirection::Reverse => false,
+            MeteorDirection::Both => self.rng.chance(0.5),
+        };
+        let speed = self.rng.range_f32(self.speeds.clone());
+        let colour = self.random_colour();
+        self.rocks[index] = Rock {
+            head: if forward { 0.0 } else { N as f32 },
+            velocity: if forward { speed } else { -speed },
+            colour,
+            alive: true,
+            wait: 0.0,
+        };
     }
 
-    fn next_line(&mut self, buf: &mut [RGB8], _dt: u32) -> Option<usize> {
-        // fade current trail randomly
+    fn update(&mut self, dt_ticks: u32) {
+        let dt_sec = dt_ticks as f32 / 1000.0;
+        // fade the trail randomly
+        let keep = libm::powf(1.0 - self.fade, dt_ticks as f32 / Self::FADE_TICKS);
         for pixel in self.current.iter_mut() {
             if (self.rng.next_u32() & 1) == 1 {
-                pixel.red *= 1.0 - self.fade;
-                pixel.green *= 1.0 - self.fade;
-                pixel.blue *= 1.0 - self.fade;
+                *pixel *= keep;
             }
         }
-        // draw meteor head and trailing solid size
-        for i in 0..self.size {
-            if self.position >= i && (self.position - i) < N {
-                self.current[self.position - i] = self.colour;
+        for i in 0..M {
+            let rock = &mut self.rocks[i];
+            if !rock.alive {
+                rock.wait -= dt_sec;
+                if rock.wait <= 0.0 {
+                    self.launch(i);
+                }
+                continue;
             }
-        }
-        self.position += 1;
-        if self.position > 2 * N {
-            if self.random_colour {
-                // random float components in [0,1)
-                let rf = (self.rng.next_u32() as f32) / (u32::MAX as f32 + 1.0);
-                let gf = (self.rng.next_u32() as f32) / (u32::MAX as f32 + 1.0);
-                let bf = (self.rng.next_u32() as f32) / (u32::MAX as f32 + 1.0);
-                self.colour = Srgb::new(rf, gf, bf);
+            let tail = rock.head;
+            rock.head += rock.velocity * dt_sec;
+            // cover everything passed this frame so fast meteors leave an unbroken trail
+            let (low, high) = if rock.velocity > 0.0 {
+                (tail.min(rock.head - self.size), rock.head)
+            } else {
+                (rock.head, tail.max(rock.head + self.size))
+            };
+            span(&mut self.current, low, high, rock.colour);
+            let gone = (rock.velocity > 0.0 && rock.head - self.size > N as f32)
+                || (rock.velocity < 0.0 && rock.head + self.size < 0.0);
+            if gone {
+                rock.alive = false;
+                rock.wait = self.rng.range_f32(self.spawn.clone());
             }
-            self.position = 0;
-        }
-        // write to output
-        let len = core::cmp::min(N, buf.len());
-        for (i, slot) in buf.iter_mut().enumerate().take(len) {
-            let p = self.current[i].into_format::<u8>();
-            *slot = RGB8 { r: p.red, g: p.green, b: p.blue };
         }
-        Some(len)
+    }
+}
+
+impl<const N: usize, const M: usize> Meteor<N, FastRng, M> {
+    /// Same as [Meteor::new] using a [FastRng] seeded with `seed`.
+    pub fn seeded(seed: u64, colour: Option<Srgb<u8>>, size: Option<usize>, fade: Option<f32>) -> Self {
+        Self::new(FastRng::new(seed), colour, size, fade)
+    }
+}
+
+impl<const N: usize, R: RngCore, const M: usize> EffectIterator for Meteor<N, R, M> {
+    fn name(&self) -> &'static str {
+        "Meteor"
+    }
+
+    fn next_line(&mut self, buf: &mut [RGB8], dt: u32) -> Option<usize> {
+        self.next_line_as(buf, dt)
     }
 
     fn pixel_count(&self) -> usize {
         N
     }
 }
+
+impl<const N: usize, R: RngCore, const M: usize> RenderAs for Meteor<N, R, M> {
+    fn next_line_as<P: Pixel>(&mut self, buf: &mut [P], dt: u32) -> Option<usize> {
+        self.update(dt);
+        let len = core::cmp::min(N, buf.len());
+        for (i, slot) in buf.iter_mut().enumerate().take(len) {
+            *slot = P::from_srgb(self.current[i]);
+        }
+        Some(len)
+    }
+}
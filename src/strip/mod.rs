@@ -4,37 +4,53 @@ mod rainbow;
 pub use rainbow::*;
 mod snow_sparkle;
 pub use snow_sparkle::*;
+mod bar_graph;
+pub use bar_graph::{BarGraph, Zones};
 mod bounce;
 pub use bounce::Bounce;
 mod christmas;
-pub use christmas::Christmas;
+pub use christmas::{Christmas, ChristmasPattern, ChristmasTheme};
 mod collision;
-pub use collision::Collision;
+pub use collision::{Collision, Particle};
 mod cycle;
 pub use cycle::Cycle;
 mod cylon;
-pub use cylon::Cylon;
+pub use cylon::{Cylon, CylonEyes, CylonTrail};
 mod fire;
-pub use fire::Fire;
+pub use fire::{Fire, FirePalette};
 mod meteor;
-pub use meteor::Meteor;
+pub use meteor::{Meteor, MeteorDirection};
 mod morse;
 pub use morse::Morse;
+mod phase_timer;
+pub use phase_timer::{Phase, PhaseTimer};
 mod progress;
-pub use progress::ProgressBar;
+pub use progress::{Fill, Indeterminate, ProgressBar};
 mod running_lights;
-pub use running_lights::RunningLights;
+pub use running_lights::{RunningLights, RunningLightsWave};
+mod spectrum;
+pub use spectrum::Spectrum;
 mod strobe;
-pub use strobe::Strobe;
+pub use strobe::{Strobe, StrobeMode};
 mod timer;
-pub use timer::Timer;
+pub use timer::{Timer, TimerEnd};
 mod twinkle;
-pub use twinkle::Twinkle;
+pub use twinkle::{Twinkle, TwinkleFox};
+mod vu_meter;
+pub use vu_meter::{VuLayout, VuMeter};
 mod wipe;
 pub use wipe::Wipe;
 
+mod draw;
 mod effects_trait;
-pub use effects_trait::EffectIterator;
+pub use effects_trait::{EffectIterator, RenderAs};
+mod flash_limiter;
+pub use flash_limiter::{FlashLimiter, FlashReport};
+mod pace;
+mod stream;
+pub use stream::{PixelStream, Pixels};
+mod sync;
+pub use sync::{Seek, Synced};
 
 // Static list available in no_alloc mode
 pub const LIST: &[&str] = &[
@@ -42,6 +58,7 @@ pub const LIST: &[&str] = &[
     "BreatheRandom",
     "Rainbow",
     "SnowSparkle",
+    "BarGraph",
     "Bounce",
     "Christmas",
     "Collision",
@@ -50,11 +67,15 @@ pub const LIST: &[&str] = &[
     "Fire",
     "Meteor",
     "Morse",
+    "PhaseTimer",
     "ProgressBar",
     "RunningLights",
+    "Spectrum",
     "Strobe",
     "Timer",
     "Twinkle",
+    "TwinkleFox",
+    "VuMeter",
     "Wipe",
 ];
 
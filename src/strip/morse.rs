@@ -1,60 +1,150 @@
-use crate::{strip::EffectIterator, RGB8};
+use crate::{morse::WORD_GAP, strip::{EffectIterator, RenderAs}, Pixel, RGB8};
+use core::ops::Range;
 
-// A minimal Morse effect that slides pre-encoded bits across the strip using Wipe-like logic,
-// but without heap. The message must be provided pre-encoded as bits slice (1=on, 0=off).
+enum Mode {
+    /// One unit per frame slides across the strip.
+    Scroll { position: usize, reverse: bool },
+    /// The segment flashes the message in real time, `dt_ticks` being milliseconds.
+    Flash {
+        unit_ms: u32,
+        elapsed_ms: u32,
+        segment: Range<usize>,
+    },
+}
+
+/// Shows a Morse message encoded as one byte per unit (1=on, 0=off), see
+/// [crate::morse::encode] to produce it from text.
+///
+/// Either scrolls the units across the strip, one pixel per unit, or flashes the message on
+/// the whole strip (or a segment of it) at a words-per-minute rate.
+///
+/// ```rust
+/// use smart_led_effects::{morse, strip::{EffectIterator, Morse}, RGB8};
+///
+/// let mut bits = [0u8; 128];
+/// let len = morse::encode("CQ DX", &mut bits).unwrap();
+/// let mut effect = Morse::<30>::flashing(&bits[..len], None, 20, None);
+/// let mut pixels = [RGB8::default(); 30];
+/// effect.next_line(&mut pixels, 16);
+/// ```
 pub struct Morse<'a, const N: usize> {
     data: &'a [u8], // sequence of 0/1
-    position: usize,
-    reverse: bool,
     colour: RGB8,
+    mode: Mode,
 }
 
 impl<'a, const N: usize> Morse<'a, N> {
+    const DEFAULT_COLOUR: RGB8 = RGB8 { r: 255, g: 0, b: 0 };
+
+    /// Scroll the message across the strip, one unit per frame.
     pub fn new_bits(data: &'a [u8], colour: Option<RGB8>, reverse: bool) -> Self {
         Self {
             data,
-            position: if reverse { N + data.len() } else { 0 },
-            reverse,
-            colour: colour.unwrap_or(RGB8 { r: 255, g: 0, b: 0 }),
+            colour: colour.unwrap_or(Self::DEFAULT_COLOUR),
+            mode: Mode::Scroll {
+                position: if reverse { N + data.len() } else { 0 },
+                reverse,
+            },
         }
     }
-}
 
-impl<'a, const N: usize> EffectIterator for Morse<'a, N> {
-    fn name(&self) -> &'static str {
-        "Morse"
+    /// Flash the message at `wpm` words per minute (PARIS timing, one unit is `1200 / wpm`
+    /// ms) on `segment` of the strip, or the whole strip if None. The message repeats after
+    /// a word gap.
+    pub fn flashing(
+        data: &'a [u8],
+        colour: Option<RGB8>,
+        wpm: u32,
+        segment: Option<Range<usize>>,
+    ) -> Self {
+        Self {
+            data,
+            colour: colour.unwrap_or(Self::DEFAULT_COLOUR),
+            mode: Mode::Flash {
+                unit_ms: Self::unit_ms(wpm),
+                elapsed_ms: 0,
+                segment: segment.unwrap_or(0..N),
+            },
+        }
     }
 
-    fn next_line(&mut self, buf: &mut [RGB8], _dt: u32) -> Option<usize> {
-        let end = N + self.data.len();
-        let pos = self.position;
+    /// Change the speed of the flashing mode. Has no effect when scrolling.
+    pub fn set_wpm(&mut self, wpm: u32) {
+        if let Mode::Flash { unit_ms, .. } = &mut self.mode {
+            *unit_ms = Self::unit_ms(wpm);
+        }
+    }
+
+    fn unit_ms(wpm: u32) -> u32 {
+        (1200 / wpm.max(1)).max(1)
+    }
+
+    fn scroll<P: Pixel>(&self, buf: &mut [P], pos: usize) -> usize {
         let len = core::cmp::min(N, buf.len());
         for (i, slot) in buf.iter_mut().enumerate().take(len) {
             let j = pos + i;
             *slot = if j < N {
-                RGB8 { r: 0, g: 0, b: 0 }
+                P::BLACK
             } else if j < N + self.data.len() {
-                if self.data[j - N] == 1 { self.colour } else { RGB8 { r: 0, g: 0, b: 0 } }
+                if self.data[j - N] == 1 { P::from_rgb8(self.colour) } else { P::BLACK }
             } else {
-                RGB8 { r: 0, g: 0, b: 0 }
+                P::BLACK
             };
         }
-        if self.reverse {
-            if self.position == 0 {
-                self.position = end;
-            } else {
-                self.position -= 1;
-            }
-        } else {
-            self.position += 1;
-            if self.position > end {
-                self.position = 0;
-            }
-        }
-        Some(len)
+        len
+    }
+}
+
+impl<'a, const N: usize> EffectIterator for Morse<'a, N> {
+    fn name(&self) -> &'static str {
+        "Morse"
+    }
+
+    fn next_line(&mut self, buf: &mut [RGB8], dt_ticks: u32) -> Option<usize> {
+        self.next_line_as(buf, dt_ticks)
     }
 
     fn pixel_count(&self) -> usize {
         N
     }
 }
+
+impl<'a, const N: usize> RenderAs for Morse<'a, N> {
+    fn next_line_as<P: Pixel>(&mut self, buf: &mut [P], dt_ticks: u32) -> Option<usize> {
+        let end = N + self.data.len();
+        match &mut self.mode {
+            Mode::Scroll { position, reverse } => {
+                let pos = *position;
+                if *reverse {
+                    if *position == 0 {
+                        *position = end;
+                    } else {
+                        *position -= 1;
+                    }
+                } else {
+                    *position += 1;
+                    if *position > end {
+                        *position = 0;
+                    }
+                }
+                Some(self.scroll(buf, pos))
+            }
+            Mode::Flash {
+                unit_ms,
+                elapsed_ms,
+                segment,
+            } => {
+                let cycle_ms = *unit_ms * (self.data.len() + WORD_GAP) as u32;
+                *elapsed_ms = (*elapsed_ms + dt_ticks % cycle_ms) % cycle_ms;
+                let unit = (*elapsed_ms / *unit_ms) as usize;
+                let on = self.data.get(unit) == Some(&1);
+                let px = if on { P::from_rgb8(self.colour) } else { P::BLACK };
+                let len = core::cmp::min(N, buf.len());
+                for (i, slot) in buf.iter_mut().enumerate().take(len) {
+                    *slot = if segment.contains(&i) { px } else { P::BLACK };
+                }
+                Some(len)
+            }
+        }
+    }
+}
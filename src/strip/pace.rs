@@ -0,0 +1,47 @@
+/// Converts elapsed ticks into whole steps, for effects that move a step at a time but can
+/// also be given a duration in ticks.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct Pace {
+    /// Ticks for `steps` steps, or None to take one step per frame.
+    ticks: Option<u32>,
+    remainder: u64,
+}
+
+impl Pace {
+    pub(crate) fn set_ticks(&mut self, ticks: Option<u32>) {
+        self.ticks = ticks.map(|t| t.max(1));
+        self.remainder = 0;
+    }
+
+    pub(crate) fn is_paced(&self) -> bool {
+        self.ticks.is_some()
+    }
+
+    /// Number of steps to take after `dt_ticks`, moving `steps` steps per period.
+    pub(crate) fn steps(&mut self, dt_ticks: u32, steps: usize) -> usize {
+        let Some(ticks) = self.ticks else {
+            return 1;
+        };
+        self.remainder += dt_ticks as u64 * steps as u64;
+        let taken = self.remainder / ticks as u64;
+        self.remainder -= taken * ticks as u64;
+        taken as usize
+    }
+
+    /// Total steps taken after `frames` calls to [Pace::steps] of `frame_ticks` each, from
+    /// a fresh start. The remainder is left as those calls would leave it.
+    pub(crate) fn seek(&mut self, frames: u64, frame_ticks: u32, steps: usize) -> u64 {
+        let Some(ticks) = self.ticks else {
+            return frames;
+        };
+        let total = frames as u128 * frame_ticks as u128 * steps as u128;
+        self.remainder = (total % ticks as u128) as u64;
+        (total / ticks as u128) as u64
+    }
+}
+
+/// Fraction of the way to move towards a target after `dt_ticks`, for smoothing with a time
+/// constant of `tau_ticks`. A first order approximation of 1 - e^(-dt / tau).
+pub(crate) fn approach(dt_ticks: u32, tau_ticks: u32) -> f32 {
+    dt_ticks as f32 / dt_ticks.saturating_add(tau_ticks).max(1) as f32
+}
@@ -0,0 +1,218 @@
+use crate::{
+    strip::{EffectIterator, RenderAs, Timer, TimerEnd},
+    Pixel, RGB8,
+};
+use palette::Srgb;
+
+/// One step of a [PhaseTimer], e.g. a 25 minute work session.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Phase {
+    pub name: &'static str,
+    pub ticks: u32,
+    pub start_colour: Srgb,
+    pub end_colour: Srgb,
+    pub gradient: bool,
+    pub count_up: bool,
+    /// Completing this phase adds a marker to the cycle counter.
+    pub counted: bool,
+}
+
+impl Phase {
+    /// A solid coloured, counting down, uncounted phase. Change the fields for other styles.
+    pub const fn new(name: &'static str, ticks: u32, colour: Srgb) -> Self {
+        Self {
+            name,
+            ticks,
+            start_colour: colour,
+            end_colour: colour,
+            gradient: false,
+            count_up: false,
+            counted: false,
+        }
+    }
+}
+
+/// Runs a [Timer] through a repeating sequence of [Phase]s, such as Pomodoro work and break
+/// sessions or interval training.
+///
+/// The strip flashes in the colour of the next phase between phases, and every completed
+/// [Phase::counted] phase lights a marker pixel at the far end of the strip.
+///
+/// ```rust
+/// use smart_led_effects::strip::{Phase, PhaseTimer};
+/// use smart_led_effects::Srgb;
+///
+/// const MINUTE: u32 = 60_000;
+/// let work = Phase { counted: true, ..Phase::new("work", 25 * MINUTE, Srgb::new(1.0, 0.0, 0.0)) };
+/// let rest = Phase::new("break", 5 * MINUTE, Srgb::new(0.0, 1.0, 0.0));
+/// let phases = [work, rest, work, rest, work, rest, work, Phase::new("long break", 15 * MINUTE, Srgb::new(0.0, 0.0, 1.0))];
+///
+/// let timer = PhaseTimer::<60>::new(&phases, None, true);
+/// assert_eq!(timer.phase().unwrap().name, "work");
+/// ```
+pub struct PhaseTimer<'a, const N: usize> {
+    phases: &'a [Phase],
+    timer: Timer<N>,
+    index: usize,
+    cycles: u32,
+    marker_colour: Option<Srgb>,
+    transition_ticks: u32,
+    transition: Option<u32>,
+}
+
+impl<'a, const N: usize> PhaseTimer<'a, N> {
+    const DEFAULT_MARKER_COLOUR: Srgb = Srgb::new(1.0, 1.0, 1.0);
+    const DEFAULT_TRANSITION: u32 = 1000;
+    const FLASH_PERIOD: u32 = 250;
+
+    /// `marker_colour` defaults to white.
+    pub fn new(phases: &'a [Phase], marker_colour: Option<Srgb>, start: bool) -> Self {
+        let mut timer = Timer::new(0, None, None, None, start);
+        timer.set_end(TimerEnd::Finish);
+        let mut me = Self {
+            phases,
+            timer,
+            index: 0,
+            cycles: 0,
+            marker_colour: Some(marker_colour.unwrap_or(Self::DEFAULT_MARKER_COLOUR)),
+            transition_ticks: Self::DEFAULT_TRANSITION,
+            transition: None,
+        };
+        me.load(0);
+        me
+    }
+
+    fn load(&mut self, index: usize) {
+        self.index = index;
+        if let Some(phase) = self.phases.get(index) {
+            self.timer.set_total_ticks(phase.ticks);
+            self.timer.set_colours(phase.start_colour, phase.end_colour);
+            self.timer.set_gradient(phase.gradient);
+            self.timer.set_count_up(phase.count_up);
+            self.timer.reset();
+        }
+    }
+
+    /// Start the sequence from the first phase.
+    pub fn start(&mut self) {
+        self.load(0);
+        self.cycles = 0;
+        self.transition = None;
+        self.timer.start();
+    }
+
+    pub fn stop(&mut self) {
+        self.timer.stop();
+    }
+
+    pub fn pause(&mut self) {
+        self.timer.pause();
+    }
+
+    pub fn resume(&mut self) {
+        self.timer.resume();
+    }
+
+    /// End the current phase now, as if its time was up.
+    pub fn skip(&mut self) {
+        self.next_phase();
+    }
+
+    fn next_phase(&mut self) {
+        if self.phases.get(self.index).is_some_and(|p| p.counted) {
+            self.cycles = self.cycles.saturating_add(1);
+        }
+        self.load((self.index + 1) % self.phases.len().max(1));
+        if self.transition_ticks > 0 {
+            self.transition = Some(0);
+        }
+    }
+
+    /// The phase running now, or the one about to start during a transition.
+    pub fn phase(&self) -> Option<&Phase> {
+        self.phases.get(self.index)
+    }
+
+    pub fn phase_index(&self) -> usize {
+        self.index
+    }
+
+    pub fn remaining_ticks(&self) -> u32 {
+        self.timer.remaining_ticks()
+    }
+
+    /// Number of [Phase::counted] phases completed since the start.
+    pub fn cycles(&self) -> u32 {
+        self.cycles
+    }
+
+    pub fn in_transition(&self) -> bool {
+        self.transition.is_some()
+    }
+
+    /// Length of the flash between phases. `0` disables it. Defaults to 1000 ticks.
+    pub fn set_transition(&mut self, ticks: u32) {
+        self.transition_ticks = ticks;
+    }
+
+    /// None hides the cycle markers.
+    pub fn set_marker_colour(&mut self, colour: Option<Srgb>) {
+        self.marker_colour = colour;
+    }
+}
+
+impl<const N: usize> EffectIterator for PhaseTimer<'_, N> {
+    fn name(&self) -> &'static str {
+        "PhaseTimer"
+    }
+
+    fn next_line(&mut self, buf: &mut [RGB8], dt_ticks: u32) -> Option<usize> {
+        self.next_line_as(buf, dt_ticks)
+    }
+
+    fn pixel_count(&self) -> usize {
+        N
+    }
+}
+
+impl<const N: usize> RenderAs for PhaseTimer<'_, N> {
+    fn next_line_as<P: Pixel>(&mut self, buf: &mut [P], dt_ticks: u32) -> Option<usize> {
+        let len = core::cmp::min(N, buf.len());
+        let buf = &mut buf[..len];
+        if self.phases.is_empty() {
+            return None;
+        }
+
+        // time past the end of a phase or flash carries on into the next, so the sequence
+        // keeps to the clock; bounded in case every phase and flash is zero ticks long
+        let mut dt_ticks = dt_ticks;
+        for _ in 0..=2 * self.phases.len() {
+            if let Some(elapsed) = self.transition {
+                let running = self.timer.is_running();
+                let elapsed = if running { elapsed.saturating_add(dt_ticks) } else { elapsed };
+                if elapsed < self.transition_ticks || !running {
+                    let colour = self.phases[self.index].start_colour;
+                    let on = elapsed % Self::FLASH_PERIOD < Self::FLASH_PERIOD / 2;
+                    let px = if on { P::from_srgb(colour) } else { P::BLACK };
+                    for slot in buf.iter_mut() { *slot = px; }
+                    self.transition = Some(elapsed);
+                    break;
+                }
+                self.transition = None;
+                dt_ticks = elapsed - self.transition_ticks;
+            }
+            if self.timer.next_line_as(buf, dt_ticks).is_some() {
+                break;
+            }
+            dt_ticks = self.timer.overrun_ticks();
+            self.next_phase();
+        }
+
+        if let Some(colour) = self.marker_colour {
+            let markers = core::cmp::min(self.cycles as usize, len);
+            let px = P::from_srgb(colour);
+            for slot in buf.iter_mut().rev().take(markers) { *slot = px; }
+        }
+        Some(len)
+    }
+}
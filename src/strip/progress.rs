@@ -1,17 +1,64 @@
-use crate::{strip::EffectIterator, RGB8};
+use crate::{strip::{pace::approach, EffectIterator, RenderAs}, Pixel, RGB8};
 use palette::{Mix, Srgb};
 
+/// Which end of the strip the bar grows from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Fill {
+    /// From index 0 towards the end.
+    Forward,
+    /// From the last index towards 0.
+    Reverse,
+    /// From the centre outwards in both directions.
+    CentreOut,
+    /// From both ends inwards, meeting in the centre.
+    EdgesIn,
+}
+
+/// Animation shown while progress is unknown.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Indeterminate {
+    /// A block bouncing between the ends.
+    Bounce,
+    /// Evenly spaced segments marching along the strip.
+    March,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum State {
+    Progress,
+    Busy(Indeterminate),
+    Error,
+    Success,
+}
+
+/// Shows a percentage as a filled bar.
+///
+/// The displayed value can ease towards the target (see [ProgressBar::set_smoothing]) and the
+/// leading pixel is lit proportionally, so slow progress moves smoothly rather than a pixel at
+/// a time. While the amount of work is unknown the bar can show an indeterminate animation,
+/// and it can end by flashing red on error or pulsing green on success.
+///
+/// Timings are in ticks, the unit of `dt_ticks`.
 pub struct ProgressBar<const N: usize> {
     start_colour: Srgb,
     end_colour: Srgb,
     gradient: bool,
-    pixels_per_percent: f32,
+    fill: Fill,
+    state: State,
+    target: f32,
     current_value: f32,
+    smoothing_ticks: u32,
+    period_ticks: u32,
+    elapsed_ticks: u32,
 }
 
 impl<const N: usize> ProgressBar<N> {
     const DEFAULT_START_COLOUR: Srgb = Srgb::new(0.0, 0.0, 1.0);
     const DEFAULT_END_COLOUR: Srgb = Srgb::new(1.0, 0.0, 0.0);
+    const ERROR_COLOUR: Srgb = Srgb::new(1.0, 0.0, 0.0);
+    const SUCCESS_COLOUR: Srgb = Srgb::new(0.0, 1.0, 0.0);
+    const DEFAULT_PERIOD: u32 = 1500;
+
     pub fn new(
         start_colour: Option<Srgb>,
         end_colour: Option<Srgb>,
@@ -21,52 +68,171 @@ impl<const N: usize> ProgressBar<N> {
             start_colour: start_colour.unwrap_or(Self::DEFAULT_START_COLOUR),
             end_colour: end_colour.unwrap_or(Self::DEFAULT_END_COLOUR),
             gradient: gradient.unwrap_or(false),
-            pixels_per_percent: N as f32 / 100.0,
+            fill: Fill::Forward,
+            state: State::Progress,
+            target: 0.0,
             current_value: 0.0,
+            smoothing_ticks: 0,
+            period_ticks: Self::DEFAULT_PERIOD,
+            elapsed_ticks: 0,
         }
     }
 
+    /// Set the target percentage (0 to 100) and leave any indeterminate or end state.
     pub fn set_percentage(&mut self, percentage: f32) {
-        self.current_value = percentage;
+        self.target = percentage.clamp(0.0, 100.0);
+        if self.state != State::Progress {
+            self.state = State::Progress;
+            self.elapsed_ticks = 0;
+        }
     }
-}
 
-impl<const N: usize> EffectIterator for ProgressBar<N> {
-    fn name(&self) -> &'static str {
-        "ProgressBar"
+    /// The percentage currently displayed, which lags the target while easing.
+    pub fn percentage(&self) -> f32 {
+        self.current_value
     }
 
-    fn next_line(&mut self, buf: &mut [RGB8], _dt: u32) -> Option<usize> {
-        let percentage = self.current_value.clamp(0.0, 100.0);
-        let pixels = N - (self.pixels_per_percent * (100.0 - percentage)) as usize;
-        let len = core::cmp::min(N, buf.len());
-        for slot in buf.iter_mut().take(len) { *slot = RGB8 { r:0, g:0, b:0 }; }
-        if self.gradient {
-            for (i, slot) in buf.iter_mut().enumerate().take(core::cmp::min(pixels, len)) {
-                let p: Srgb<u8> = self
-                    .start_colour
-                    .mix(self.end_colour, i as f32 / N as f32)
-                    .into_format();
-                *slot = RGB8 {
-                    r: p.red,
-                    g: p.green,
-                    b: p.blue,
-                };
+    /// Ease the displayed value towards the target with a time constant of `ticks`.
+    /// `0` (the default) jumps straight to the target.
+    pub fn set_smoothing(&mut self, ticks: u32) {
+        self.smoothing_ticks = ticks;
+    }
+
+    pub fn set_fill(&mut self, fill: Fill) {
+        self.fill = fill;
+    }
+
+    /// Period of the indeterminate, error and success animations. Defaults to 1500 ticks.
+    pub fn set_period(&mut self, ticks: u32) {
+        self.period_ticks = ticks.max(2);
+    }
+
+    /// Show an animation until the next [ProgressBar::set_percentage].
+    pub fn set_indeterminate(&mut self, style: Indeterminate) {
+        self.enter(State::Busy(style));
+    }
+
+    /// Flash red until the next [ProgressBar::set_percentage].
+    pub fn fail(&mut self) {
+        self.enter(State::Error);
+    }
+
+    /// Pulse green until the next [ProgressBar::set_percentage].
+    pub fn succeed(&mut self) {
+        self.enter(State::Success);
+    }
+
+    fn enter(&mut self, state: State) {
+        self.state = state;
+        self.elapsed_ticks = 0;
+    }
+
+    /// Position of pixel `i` along the fill direction, and the length of that direction.
+    fn fill_position(&self, i: usize) -> (f32, f32) {
+        match self.fill {
+            Fill::Forward => (i as f32, N as f32),
+            Fill::Reverse => ((N - 1 - i) as f32, N as f32),
+            Fill::CentreOut => {
+                let half = N as f32 / 2.0;
+                let dist = ((i as f32 + 0.5 - half).abs() - 0.5).max(0.0);
+                (dist, half)
             }
+            Fill::EdgesIn => (i.min(N - 1 - i) as f32, N as f32 / 2.0),
+        }
+    }
+
+    /// Triangle wave from 0 to 1 and back over one period.
+    fn triangle(&self) -> f32 {
+        let half = self.period_ticks / 2;
+        let t = self.elapsed_ticks % self.period_ticks;
+        if t < half {
+            t as f32 / half as f32
         } else {
-            let mix = percentage / 100.0;
-            let p: Srgb<u8> = self.start_colour.mix(self.end_colour, mix).into_format();
-            let px = RGB8 {
-                r: p.red,
-                g: p.green,
-                b: p.blue,
+            (self.period_ticks - t) as f32 / half as f32
+        }
+    }
+
+    fn render_progress<P: Pixel>(&self, buf: &mut [P]) {
+        let fraction = self.current_value / 100.0;
+        let uniform = self.start_colour.mix(self.end_colour, fraction);
+        for (i, slot) in buf.iter_mut().enumerate() {
+            let (position, extent) = self.fill_position(i);
+            let lit = (fraction * extent - position).clamp(0.0, 1.0);
+            let colour = if self.gradient {
+                self.start_colour.mix(self.end_colour, position / extent)
+            } else {
+                uniform
             };
-            for slot in buf.iter_mut().take(core::cmp::min(pixels, len)) { *slot = px; }
+            *slot = P::from_srgb(colour * lit);
         }
-        Some(len)
+    }
+
+    fn render_busy<P: Pixel>(&self, buf: &mut [P], style: Indeterminate) {
+        match style {
+            Indeterminate::Bounce => {
+                let width = (N / 5).max(1);
+                let travel = N.saturating_sub(width) as f32;
+                let start = self.triangle() * travel;
+                for (i, slot) in buf.iter_mut().enumerate() {
+                    let i = i as f32;
+                    // anti-alias both edges of the block
+                    let lit = (i + 1.0 - start).min(start + width as f32 - i).clamp(0.0, 1.0);
+                    *slot = P::from_srgb(self.start_colour * lit);
+                }
+            }
+            Indeterminate::March => {
+                let segment = (N / 10).max(2);
+                let pattern = 2 * segment;
+                let offset = (self.elapsed_ticks % self.period_ticks) as usize * pattern
+                    / self.period_ticks as usize;
+                for (i, slot) in buf.iter_mut().enumerate() {
+                    let on = (i + pattern - offset) % pattern < segment;
+                    *slot = if on { P::from_srgb(self.start_colour) } else { P::BLACK };
+                }
+            }
+        }
+    }
+}
+
+impl<const N: usize> EffectIterator for ProgressBar<N> {
+    fn name(&self) -> &'static str {
+        "ProgressBar"
+    }
+
+    fn next_line(&mut self, buf: &mut [RGB8], dt_ticks: u32) -> Option<usize> {
+        self.next_line_as(buf, dt_ticks)
     }
 
     fn pixel_count(&self) -> usize {
         N
     }
 }
+
+impl<const N: usize> RenderAs for ProgressBar<N> {
+    fn next_line_as<P: Pixel>(&mut self, buf: &mut [P], dt_ticks: u32) -> Option<usize> {
+        self.elapsed_ticks = self.elapsed_ticks.wrapping_add(dt_ticks);
+        if self.smoothing_ticks == 0 {
+            self.current_value = self.target;
+        } else {
+            let alpha = approach(dt_ticks, self.smoothing_ticks);
+            self.current_value += (self.target - self.current_value) * alpha;
+        }
+
+        let len = core::cmp::min(N, buf.len());
+        let buf = &mut buf[..len];
+        match self.state {
+            State::Progress => self.render_progress(buf),
+            State::Busy(style) => self.render_busy(buf, style),
+            State::Error => {
+                let on = self.elapsed_ticks % self.period_ticks < self.period_ticks / 2;
+                let px = if on { P::from_srgb(Self::ERROR_COLOUR) } else { P::BLACK };
+                for slot in buf.iter_mut() { *slot = px; }
+            }
+            State::Success => {
+                let px = P::from_srgb(Self::SUCCESS_COLOUR * self.triangle());
+                for slot in buf.iter_mut() { *slot = px; }
+            }
+        }
+        Some(len)
+    }
+}
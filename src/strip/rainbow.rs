@@ -1,32 +1,71 @@
-use crate::{strip::EffectIterator, RGB8};
-use palette::{Hsv, ShiftHueAssign};
+use crate::{
+    strip::{EffectIterator, PixelStream, RenderAs, Seek},
+    Pixel, RGB8,
+};
 
+/// Rainbow spread across the strip that rotates through the hue wheel.
+///
+/// Only the hue of the first pixel is stored; every other pixel is offset from it by
+/// `1 / N` of a turn, so the effect can be streamed without an `N` sized state array.
+/// Hues are kept as a 16-bit fraction of a full turn so the `int-math` path needs no floats.
 pub struct Rainbow<const N: usize> {
-    last_state: [Hsv; N],
-    step_size: f32,
+    hue: u16,
+    separation: u32,
+    step_size: u16,
 }
 
 impl<const N: usize> Rainbow<N> {
     pub fn new(steps: Option<usize>) -> Self {
-        let step = steps.unwrap_or(360);
-        let step_size = 360.0 / step as f32;
-        let separation = 360.0 / N as f32;
-        let mut color = Hsv::new(0.0, 1.0, 1.0);
-        let mut arr: [Hsv; N] = core::array::from_fn(|i| {
-            if i > 0 {
-                color.shift_hue_assign(separation);
-            }
-            color
-        });
-        // Ensure the first element is Hsv::new(0,1,1)
-        arr[0] = Hsv::new(0.0, 1.0, 1.0);
+        let step = steps.unwrap_or(360).max(1);
         Self {
-            last_state: arr,
-            step_size,
+            hue: 0,
+            separation: 0x1_0000 / N.max(1) as u32,
+            step_size: (0x1_0000 / step) as u16,
         }
     }
 
     pub fn new_default() -> Self { Self::new(None) }
+
+    fn hue_at(&self, index: usize) -> u16 {
+        self.hue
+            .wrapping_add((self.separation.wrapping_mul(index as u32)) as u16)
+    }
+
+    #[cfg(not(feature = "int-math"))]
+    fn hsv_at(&self, index: usize) -> palette::Hsv {
+        palette::Hsv::new(self.hue_at(index) as f32 * (360.0 / 65536.0), 1.0, 1.0)
+    }
+}
+
+impl<const N: usize> PixelStream for Rainbow<N> {
+    fn advance(&mut self, _dt: u32) {
+        self.hue = self.hue.wrapping_add(self.step_size);
+    }
+
+    #[cfg(not(feature = "int-math"))]
+    fn pixel<P: Pixel>(&self, index: usize) -> P {
+        P::from_hsv(self.hsv_at(index))
+    }
+
+    #[cfg(feature = "int-math")]
+    fn pixel<P: Pixel>(&self, index: usize) -> P {
+        use crate::math8::{hsv2rgb_rainbow, Hsv8};
+        let hue = (self.hue_at(index) >> 8) as u8;
+        P::from_rgb8(hsv2rgb_rainbow(Hsv8::new(hue, 255, 255)))
+    }
+
+    #[cfg(not(feature = "int-math"))]
+    fn render<P: Pixel>(&self, buf: &mut [P]) -> usize {
+        let len = core::cmp::min(N, buf.len());
+        crate::utils::render_hsv(&mut buf[..len], |i| self.hsv_at(i));
+        len
+    }
+}
+
+impl<const N: usize> Seek for Rainbow<N> {
+    fn seek(&mut self, step: u64, _step_ticks: u32, _seed: u64) {
+        self.hue = (step as u16).wrapping_mul(self.step_size);
+    }
 }
 
 impl<const N: usize> EffectIterator for Rainbow<N> {
@@ -34,18 +73,18 @@ impl<const N: usize> EffectIterator for Rainbow<N> {
         "Rainbow"
     }
 
-    fn next_line(&mut self, buf: &mut [RGB8], _dt: u32) -> Option<usize> {
-        for pixel in self.last_state.iter_mut() {
-            pixel.shift_hue_assign(self.step_size);
-        }
-        let len = core::cmp::min(N, buf.len());
-        for (i, slot) in buf.iter_mut().enumerate().take(len) {
-            *slot = crate::utils::hsv_to_rgb8_pixel(self.last_state[i]);
-        }
-        Some(len)
+    fn next_line(&mut self, buf: &mut [RGB8], dt: u32) -> Option<usize> {
+        self.next_line_as(buf, dt)
     }
 
     fn pixel_count(&self) -> usize {
         N
     }
 }
+
+impl<const N: usize> RenderAs for Rainbow<N> {
+    fn next_line_as<P: Pixel>(&mut self, buf: &mut [P], dt: u32) -> Option<usize> {
+        self.advance(dt);
+        Some(self.render(buf))
+    }
+}
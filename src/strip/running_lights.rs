@@ -1,27 +1,224 @@
-use crate::{strip::EffectIterator, utils::hsv_to_rgb8_pixel, RGB8};
+use crate::{
+    math8::ease16_in_out_sine,
+    strip::{pace::Pace, EffectIterator, PixelStream, RenderAs, Seek},
+    Pixel, RGB8,
+};
 use palette::Hsv;
 
+/// Sub-steps per pixel, for fractional wavelengths and speeds.
+const SUBSTEPS: u32 = 256;
+
+/// Brightness shape of each [RunningLights] wave.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum RunningLightsWave {
+    /// Ramps up and down in straight lines.
+    #[default]
+    Triangle,
+    Sine,
+    /// Hard on for the middle half of the wave, off for the rest.
+    Square,
+    /// Ramps up then drops straight back to dark.
+    Sawtooth,
+    /// Theater chase: only the first pixel of each wavelength is lit, so every Nth pixel
+    /// shows and the pattern shifts a whole pixel at a time.
+    Chase,
+}
+
+/// Waves of light running along the strip.
+///
+/// By default the waves move one pixel per frame. [RunningLights::set_speed] sets a speed in
+/// pixels per second instead (with ticks in milliseconds) and [RunningLights::set_cycle_ticks]
+/// the time to move one wavelength; both move in fractions of a pixel. Successive waves can
+/// take turns through a list of colours, see [RunningLights::set_colours].
+///
+/// # Arguments
+///
+/// - `N` - The number of LEDs in the strip.
+/// - `colour` - The wave colour, white if None.
+/// - `reverse` - Run towards the far end instead of towards index 0.
+/// - `period` - The wavelength in pixels, `N` if None, see [RunningLights::set_wavelength].
 pub struct RunningLights<const N: usize> {
+    #[cfg(not(feature = "int-math"))]
     colour: Hsv,
-    position: usize,
+    #[cfg(feature = "int-math")]
+    peak: RGB8,
+    colours: Option<&'static [RGB8]>,
+    wave: RunningLightsWave,
+    /// Offset of the pattern, in sub-steps.
+    offset: u32,
     reverse: bool,
-    period: usize,
+    /// Wavelength in sub-steps.
+    wavelength: u32,
+    /// Speed in sub-steps per second, when set.
+    speed: Option<u32>,
+    pace: Pace,
 }
 
 impl<const N: usize> RunningLights<N> {
     pub fn new(colour: Option<RGB8>, reverse: bool, period: Option<usize>) -> Self {
+        let colour = match colour {
+            Some(rgb) => crate::utils::rgb8_to_hsv(rgb),
+            None => Hsv::new(0.0, 0.0, 1.0),
+        };
         Self {
-            colour: match colour {
-                Some(rgb) => crate::utils::rgb8_to_hsv(rgb),
-                None => Hsv::new(0.0, 0.0, 1.0),
-            },
-            position: if reverse { N } else { 0 },
+            #[cfg(not(feature = "int-math"))]
+            colour,
+            #[cfg(feature = "int-math")]
+            peak: crate::utils::hsv_to_rgb8_pixel(Hsv::new(colour.hue, colour.saturation, 1.0)),
+            colours: None,
+            wave: RunningLightsWave::default(),
+            offset: 0,
             reverse,
-            period: period.unwrap_or(N.max(1)),
+            wavelength: period.unwrap_or(N).max(1) as u32 * SUBSTEPS,
+            speed: None,
+            pace: Pace::default(),
         }
     }
 
     pub fn white(reverse: bool) -> Self { Self::new(Some(RGB8 { r:255, g:255, b:255 }), reverse, None) }
+
+    /// Classic theater chase: every `spacing`th pixel lit, shifting along one pixel per frame.
+    pub fn theater_chase(colour: Option<RGB8>, spacing: usize) -> Self {
+        let mut me = Self::new(colour, false, Some(spacing));
+        me.set_wave(RunningLightsWave::Chase);
+        me
+    }
+
+    /// Time for the pattern to move one wavelength along the strip, e.g.
+    /// [crate::beat::BeatClock::beat_ticks]. None (the default) moves one pixel per frame.
+    /// Replaces any speed from [RunningLights::set_speed]. This only follows the tempo; use
+    /// [RunningLights::set_phase] to line the waves up with the beat.
+    pub fn set_cycle_ticks(&mut self, ticks: Option<u32>) {
+        self.speed = None;
+        self.pace.set_ticks(ticks);
+    }
+
+    /// Move the waves to `phase` (0 to 1) of a wavelength along, keeping the current colours,
+    /// e.g. `lights.set_phase(clock.beat_phase(now))` with a [crate::beat::BeatClock].
+    pub fn set_phase(&mut self, phase: f32) {
+        let along = (phase.clamp(0.0, 1.0) * self.wavelength as f32) as u32 % self.wavelength;
+        let along = if self.reverse { (self.wavelength - along) % self.wavelength } else { along };
+        self.offset = self.offset / self.wavelength * self.wavelength + along;
+    }
+
+    /// Speed in pixels per second, or None (the default) to move one pixel per frame.
+    /// Replaces any period from [RunningLights::set_cycle_ticks].
+    pub fn set_speed(&mut self, pixels_per_second: Option<f32>) {
+        self.speed = pixels_per_second.map(|speed| (speed.max(0.0) * SUBSTEPS as f32) as u32);
+        self.pace.set_ticks(self.speed.map(|_| 1000));
+    }
+
+    /// Length of one wave in pixels, which need not be whole.
+    pub fn set_wavelength(&mut self, pixels: f32) {
+        self.wavelength = (pixels.max(1.0) * SUBSTEPS as f32) as u32;
+        self.offset %= self.cycle();
+    }
+
+    pub fn set_wave(&mut self, wave: RunningLightsWave) {
+        self.wave = wave;
+    }
+
+    /// Colours successive waves take in turn, e.g. red, white and blue, instead of the single
+    /// colour.
+    pub fn set_colours(&mut self, colours: Option<&'static [RGB8]>) {
+        self.colours = colours.filter(|c| !c.is_empty());
+        self.offset %= self.cycle();
+    }
+
+    /// Length of the whole pattern, one wave per colour, in sub-steps.
+    fn cycle(&self) -> u32 {
+        self.wavelength * self.colours.map_or(1, |c| c.len() as u32)
+    }
+
+    fn shift(&mut self, steps: u32) {
+        let cycle = self.cycle();
+        let steps = steps % cycle;
+        self.offset = if self.reverse {
+            (self.offset + cycle - steps) % cycle
+        } else {
+            (self.offset + steps) % cycle
+        };
+    }
+
+    /// Brightness of pixel `index` as a fraction of 65535, and which wave it is in.
+    fn level(&self, index: usize) -> (u16, usize) {
+        let position = match self.wave {
+            // a chase moves a whole pixel at a time
+            RunningLightsWave::Chase => index as u32 * SUBSTEPS + self.offset / SUBSTEPS * SUBSTEPS,
+            _ => index as u32 * SUBSTEPS + self.offset,
+        } % self.cycle();
+        let wave = (position / self.wavelength) as usize;
+        let phase = position % self.wavelength;
+        let fraction = (phase as u64 * 65536 / self.wavelength as u64) as u32;
+        let triangle = if fraction < 32768 { 2 * fraction } else { 2 * (65536 - fraction) };
+        let triangle = triangle.min(65535) as u16;
+        let level = match self.wave {
+            RunningLightsWave::Triangle => triangle,
+            RunningLightsWave::Sine => ease16_in_out_sine(triangle),
+            RunningLightsWave::Square => if (16384..49152).contains(&fraction) { u16::MAX } else { 0 },
+            RunningLightsWave::Sawtooth => fraction as u16,
+            RunningLightsWave::Chase => if phase < SUBSTEPS { u16::MAX } else { 0 },
+        };
+        (level, wave)
+    }
+}
+
+impl<const N: usize> RunningLights<N> {
+    #[cfg(not(feature = "int-math"))]
+    fn hsv_at(&self, index: usize) -> Hsv {
+        let (level, wave) = self.level(index);
+        let mut hsv = match self.colours {
+            Some(colours) => crate::utils::rgb8_to_hsv(colours[wave % colours.len()]),
+            None => Hsv::new(self.colour.hue, self.colour.saturation, 1.0),
+        };
+        hsv.value *= level as f32 / 65535.0;
+        hsv
+    }
+}
+
+impl<const N: usize> PixelStream for RunningLights<N> {
+    fn advance(&mut self, dt: u32) {
+        let steps = match self.speed {
+            Some(speed) => self.pace.steps(dt, speed as usize) as u32,
+            None if self.pace.is_paced() => self.pace.steps(dt, self.wavelength as usize) as u32,
+            None => SUBSTEPS,
+        };
+        self.shift(steps);
+    }
+
+    #[cfg(not(feature = "int-math"))]
+    fn pixel<P: Pixel>(&self, index: usize) -> P {
+        P::from_hsv(self.hsv_at(index))
+    }
+
+    #[cfg(not(feature = "int-math"))]
+    fn render<P: Pixel>(&self, buf: &mut [P]) -> usize {
+        let len = core::cmp::min(N, buf.len());
+        crate::utils::render_hsv(&mut buf[..len], |i| self.hsv_at(i));
+        len
+    }
+
+    #[cfg(feature = "int-math")]
+    fn pixel<P: Pixel>(&self, index: usize) -> P {
+        let (level, wave) = self.level(index);
+        let peak = match self.colours {
+            Some(colours) => colours[wave % colours.len()],
+            None => self.peak,
+        };
+        P::from_rgb8(crate::math8::scale8_rgb(peak, (level >> 8) as u8))
+    }
+}
+
+impl<const N: usize> Seek for RunningLights<N> {
+    fn seek(&mut self, step: u64, step_ticks: u32, _seed: u64) {
+        let steps = match self.speed {
+            Some(speed) => self.pace.seek(step, step_ticks, speed as usize),
+            None if self.pace.is_paced() => self.pace.seek(step, step_ticks, self.wavelength as usize),
+            None => step * SUBSTEPS as u64,
+        };
+        self.offset = 0;
+        self.shift((steps % self.cycle() as u64) as u32);
+    }
 }
 
 impl<const N: usize> EffectIterator for RunningLights<N> {
@@ -29,36 +226,18 @@ impl<const N: usize> EffectIterator for RunningLights<N> {
         "RunningLights"
     }
 
-    fn next_line(&mut self, buf: &mut [RGB8], _dt: u32) -> Option<usize> {
-        let len = core::cmp::min(N, buf.len());
-        for (i, slot) in buf.iter_mut().enumerate().take(len) {
-            let phase = (i + self.position) % self.period;
-            let half = self.period / 2;
-            let brightness = if phase < half {
-                (phase as f32) / (half as f32)
-            } else {
-                ((self.period - phase) as f32) / (half as f32)
-            };
-            let mut hsv = self.colour;
-            hsv.value = brightness.clamp(0.0, 1.0);
-            *slot = hsv_to_rgb8_pixel(hsv);
-        }
-        if self.reverse {
-            if self.position == 0 {
-                self.position = self.period;
-            } else {
-                self.position -= 1;
-            }
-        } else {
-            self.position += 1;
-            if self.position >= self.period {
-                self.position = 0;
-            }
-        }
-        Some(len)
+    fn next_line(&mut self, buf: &mut [RGB8], dt: u32) -> Option<usize> {
+        self.next_line_as(buf, dt)
     }
 
     fn pixel_count(&self) -> usize {
         N
     }
 }
+
+impl<const N: usize> RenderAs for RunningLights<N> {
+    fn next_line_as<P: Pixel>(&mut self, buf: &mut [P], dt: u32) -> Option<usize> {
+        self.advance(dt);
+        Some(self.render(buf))
+    }
+}
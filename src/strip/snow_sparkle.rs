@@ -1,8 +1,19 @@
-use crate::{strip::EffectIterator, RGB8};
+use crate::{
+    rng::{hash32, FastRng, RandomExt},
+    strip::{EffectIterator, PixelStream, RenderAs, Seek},
+    Pixel, RGB8,
+};
 use palette::Hsv;
 use rand_core::RngCore;
 
-pub struct SnowSparkle<const N: usize, R: RngCore> {
+/// Most frames [Seek] replays to rebuild the sparkles still fading.
+const MAX_REPLAY: u64 = 256;
+
+/// Sparkles flashing over a dim base colour.
+///
+/// With [Seek] the sparkles of each frame come from the seed and the frame number, and the
+/// frames a sparkle takes to fade are replayed, at most 256 of them.
+pub struct SnowSparkle<const N: usize, R: RngCore = FastRng> {
     frequency: u8,
     probability: f32,
     fade: f32,
@@ -54,15 +65,22 @@ impl<const N: usize, R: RngCore> SnowSparkle<N, R> {
 
     pub fn white(rng: R) -> Self { Self::sparkle(rng, Some(RGB8 { r: 255, g: 255, b: 255 })) }
 
-    fn generate_sparkle(&mut self) {
-        let idx = (self.rng.next_u32() as usize) % N;
-        let mut sparkle = self.colour;
-        // random value in [0.5, 1.0)
-        let v = 0.5 + (self.rng.next_u32() as f32 / u32::MAX as f32) * 0.5;
-        sparkle.value = v;
-        let chance = self.rng.next_u32() as f32 / u32::MAX as f32;
-        if chance < self.probability {
-            self.current[idx] = sparkle;
+    /// Fade the sparkles and add this frame's new ones, drawn from `replay` when seeking.
+    fn frame(&mut self, replay: Option<&mut FastRng>) {
+        self.fade_sparkles();
+        let rng: &mut dyn RngCore = match replay {
+            Some(rng) => rng,
+            None => &mut self.rng,
+        };
+        // 0..frequency random sparkles per frame
+        let chances = (rng.next_u32() % self.frequency.max(1) as u32) as u8;
+        for _ in 0..chances {
+            let idx = rng.index(N);
+            let mut sparkle = self.colour;
+            sparkle.value = rng.range_f32(0.5..1.0);
+            if rng.chance(self.probability) {
+                self.current[idx] = sparkle;
+            }
         }
     }
 
@@ -73,26 +91,64 @@ impl<const N: usize, R: RngCore> SnowSparkle<N, R> {
     }
 }
 
+impl<const N: usize> SnowSparkle<N> {
+    /// Same as [SnowSparkle::new] using a [FastRng] seeded with `seed`.
+    pub fn seeded(
+        seed: u64,
+        colour: Option<RGB8>,
+        sparkle: Option<u8>,
+        probability: Option<f32>,
+        fade: Option<f32>,
+    ) -> Self {
+        Self::new(FastRng::new(seed), colour, sparkle, probability, fade)
+    }
+}
+
+impl<const N: usize, R: RngCore> PixelStream for SnowSparkle<N, R> {
+    fn advance(&mut self, _dt: u32) {
+        self.frame(None);
+    }
+
+    fn pixel<P: Pixel>(&self, index: usize) -> P {
+        P::from_hsv(self.current[index])
+    }
+
+    fn render<P: Pixel>(&self, buf: &mut [P]) -> usize {
+        let len = core::cmp::min(N, buf.len());
+        P::from_hsv_slice(&self.current[..len], &mut buf[..len]);
+        len
+    }
+}
+
+impl<const N: usize, R: RngCore> Seek for SnowSparkle<N, R> {
+    fn seek(&mut self, step: u64, _step_ticks: u32, seed: u64) {
+        // a sparkle is back to the base brightness (1 - base) / fade frames after it appeared
+        let fading = ((1.0 - Self::BASE_BRIGHTNESS) / self.fade) as u64;
+        let frames = fading.saturating_add(1).min(MAX_REPLAY);
+        self.current = [self.colour; N];
+        for frame in step.saturating_sub(frames) + 1..=step {
+            self.frame(Some(&mut FastRng::new(hash32(seed, frame) as u64)));
+        }
+    }
+}
+
 impl<const N: usize, R: RngCore> EffectIterator for SnowSparkle<N, R> {
     fn name(&self) -> &'static str {
         "SnowSparkle"
     }
 
-    fn next_line(&mut self, buf: &mut [RGB8], _dt: u32) -> Option<usize> {
-        self.fade_sparkles();
-        // 0..frequency random sparkles per frame
-        let chances = (self.rng.next_u32() % self.frequency as u32) as u8;
-        for _ in 0..chances {
-            self.generate_sparkle();
-        }
-        let len = core::cmp::min(N, buf.len());
-        for (i, slot) in buf.iter_mut().enumerate().take(len) {
-            *slot = crate::utils::hsv_to_rgb8_pixel(self.current[i]);
-        }
-        Some(len)
+    fn next_line(&mut self, buf: &mut [RGB8], dt: u32) -> Option<usize> {
+        self.next_line_as(buf, dt)
     }
 
     fn pixel_count(&self) -> usize {
         N
     }
 }
+
+impl<const N: usize, R: RngCore> RenderAs for SnowSparkle<N, R> {
+    fn next_line_as<P: Pixel>(&mut self, buf: &mut [P], dt: u32) -> Option<usize> {
+        self.advance(dt);
+        Some(self.render(buf))
+    }
+}
@@ -0,0 +1,76 @@
+use crate::{strip::{pace::approach, EffectIterator, RenderAs}, Pixel, RGB8};
+use palette::Hsv;
+
+/// Spectrum analyser: the strip is split into `B` frequency bands, bass at index 0, each
+/// glowing with its level and coloured from red (bass) to violet (treble).
+///
+/// Feed it band amplitudes, e.g. [crate::audio::Analyser::spectrum], with
+/// [Spectrum::set_spectrum]. Levels rise immediately and fall back with the decay time
+/// constant, in ticks.
+pub struct Spectrum<const N: usize, const B: usize> {
+    levels: [f32; B],
+    floor_db: f32,
+    decay_ticks: u32,
+}
+
+impl<const N: usize, const B: usize> Spectrum<N, B> {
+    const DEFAULT_FLOOR_DB: f32 = -60.0;
+    const DEFAULT_DECAY: u32 = 150;
+    /// Hue of the highest band; the lowest is red.
+    const TREBLE_HUE: f32 = 270.0;
+
+    /// `floor_db` is the amplitude shown as dark, relative to full scale (-60 dB by default).
+    /// `decay_ticks` defaults to 150.
+    pub fn new(floor_db: Option<f32>, decay_ticks: Option<u32>) -> Self {
+        Self {
+            levels: [0.0; B],
+            floor_db: floor_db.unwrap_or(Self::DEFAULT_FLOOR_DB).min(-1.0),
+            decay_ticks: decay_ticks.unwrap_or(Self::DEFAULT_DECAY),
+        }
+    }
+
+    /// Set the band amplitudes, 1.0 being full scale. Extra values are ignored.
+    pub fn set_spectrum(&mut self, amplitudes: &[f32]) {
+        for (level, amplitude) in self.levels.iter_mut().zip(amplitudes) {
+            let db = 20.0 * libm::log10f(amplitude.max(1e-9));
+            let value = (1.0 - db / self.floor_db).clamp(0.0, 1.0);
+            *level = level.max(value);
+        }
+    }
+
+    /// The displayed level of each band, 0 to 1.
+    pub fn levels(&self) -> &[f32; B] {
+        &self.levels
+    }
+}
+
+impl<const N: usize, const B: usize> EffectIterator for Spectrum<N, B> {
+    fn name(&self) -> &'static str {
+        "Spectrum"
+    }
+
+    fn next_line(&mut self, buf: &mut [RGB8], dt_ticks: u32) -> Option<usize> {
+        self.next_line_as(buf, dt_ticks)
+    }
+
+    fn pixel_count(&self) -> usize {
+        N
+    }
+}
+
+impl<const N: usize, const B: usize> RenderAs for Spectrum<N, B> {
+    fn next_line_as<P: Pixel>(&mut self, buf: &mut [P], dt_ticks: u32) -> Option<usize> {
+        let len = core::cmp::min(N, buf.len());
+        for (i, slot) in buf.iter_mut().enumerate().take(len) {
+            let band = i * B / N;
+            let hue = band as f32 * Self::TREBLE_HUE / (B.max(2) - 1) as f32;
+            *slot = self.levels.get(band).map_or(P::BLACK, |level| P::from_hsv(Hsv::new(hue, 1.0, *level)));
+        }
+
+        let keep = 1.0 - approach(dt_ticks, self.decay_ticks);
+        for level in self.levels.iter_mut() {
+            *level *= keep;
+        }
+        Some(len)
+    }
+}
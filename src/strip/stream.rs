@@ -0,0 +1,93 @@
+use crate::{strip::EffectIterator, Pixel, RGB8};
+use core::marker::PhantomData;
+use smart_leds_trait::SmartLedsWrite;
+
+/// Effects whose pixels can be computed one at a time from compact state.
+///
+/// This lets RAM-constrained targets skip the output buffer entirely and hand an iterator
+/// straight to a [SmartLedsWrite] driver:
+///
+/// ```rust
+/// use smart_led_effects::strip::{PixelStream, Rainbow};
+///
+/// let mut effect = Rainbow::<150>::new(None);
+/// for pixel in effect.stream(16) {
+///     // push `pixel` to the strip
+/// #   let _ = pixel;
+/// }
+/// ```
+pub trait PixelStream: EffectIterator {
+    /// Advance the effect by `dt_ticks` without rendering anything.
+    fn advance(&mut self, dt_ticks: u32);
+
+    /// Colour of pixel `index` for the current state.
+    fn pixel<P: Pixel>(&self, index: usize) -> P;
+
+    /// Render the current state into `buf` without advancing.
+    /// Returns the number of pixels written.
+    fn render<P: Pixel>(&self, buf: &mut [P]) -> usize {
+        let len = core::cmp::min(self.pixel_count(), buf.len());
+        for (i, slot) in buf.iter_mut().enumerate().take(len) {
+            *slot = self.pixel(i);
+        }
+        len
+    }
+
+    /// Advance by `dt_ticks` and iterate over the new frame as [RGB8].
+    fn stream(&mut self, dt_ticks: u32) -> Pixels<'_, Self, RGB8>
+    where
+        Self: Sized,
+    {
+        self.stream_as(dt_ticks)
+    }
+
+    /// Advance by `dt_ticks` and iterate over the new frame as any [Pixel] type.
+    fn stream_as<P: Pixel>(&mut self, dt_ticks: u32) -> Pixels<'_, Self, P>
+    where
+        Self: Sized,
+    {
+        self.advance(dt_ticks);
+        Pixels {
+            effect: self,
+            index: 0,
+            _pixel: PhantomData,
+        }
+    }
+
+    /// Advance by `dt_ticks` and write the new frame to `writer` without an output buffer.
+    fn write_to<W>(&mut self, writer: &mut W, dt_ticks: u32) -> Result<(), W::Error>
+    where
+        Self: Sized,
+        W: SmartLedsWrite,
+        RGB8: Into<W::Color>,
+    {
+        writer.write(self.stream(dt_ticks))
+    }
+}
+
+/// Iterator over one frame of a [PixelStream] effect, see [PixelStream::stream].
+pub struct Pixels<'a, E: PixelStream, P: Pixel> {
+    effect: &'a E,
+    index: usize,
+    _pixel: PhantomData<P>,
+}
+
+impl<E: PixelStream, P: Pixel> Iterator for Pixels<'_, E, P> {
+    type Item = P;
+
+    fn next(&mut self) -> Option<P> {
+        if self.index >= self.effect.pixel_count() {
+            return None;
+        }
+        let pixel = self.effect.pixel(self.index);
+        self.index += 1;
+        Some(pixel)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let remaining = self.effect.pixel_count().saturating_sub(self.index);
+        (remaining, Some(remaining))
+    }
+}
+
+impl<E: PixelStream, P: Pixel> ExactSizeIterator for Pixels<'_, E, P> {}
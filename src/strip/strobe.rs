@@ -1,38 +1,72 @@
-use super::EffectIterator;
+use super::{EffectIterator, PixelStream, RenderAs, Seek};
+use crate::{
+    rng::{hash32, FastRng, RandomExt},
+    Pixel, RGB8,
+};
+use core::f32::consts::PI;
 use palette::{FromColor, Hsv, Srgb};
 use rand_core::RngCore;
 
+/// Bursts [Seek] replays to find flashes skipped by the maximum frequency.
+const EPOCH: u64 = 8;
+
+/// How each [Strobe] flash looks.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum StrobeMode {
+    /// Full brightness, fading by `decay` every frame.
+    #[default]
+    Decay,
+    /// Hard on for this fraction of the period, then off.
+    Duty(f32),
+    /// A beam sweeping along the strip once per period, like a rotating beacon. Bursts and
+    /// segments are ignored.
+    Beacon,
+}
+
 /// Strobe effect
 ///
 /// This effect flashes the whole strip in a given colour or random colour if None is supplied.
+/// Flashes can come in bursts with a pause between them, and the strip can be split into
+/// segments that take turns, see [Strobe::police] and the other presets.
+///
+/// [Seek] follows the automatic schedule, ignoring [Strobe::trigger]. With a maximum frequency
+/// it replays at most 8 bursts, assuming the flash before them was shown, so it may skip a
+/// flash that running the strobe would show, but never shows flashes too close together.
 ///
 /// # Arguments
 ///
-/// * `count` - The number of pixels in the strip.
+/// * `N` - The number of pixels in the strip.
+/// * `rng` - The random number source used for random colours, see also [Strobe::seeded].
 /// * `colour` - The colour to flash. If `None` a random colour will be used.
-/// * `period` - The period of the strobe.
-/// * `decay` - The rate at which the colour fades. If `None` the default value of `0.02` per tick will be used.
+/// * `period_ticks` - The time from the start of one flash to the next.
+/// * `decay` - The rate at which the colour fades. If `None` the default value of `0.02` per frame will be used.
 ///
 /// # Examples
 ///
 /// ```
-/// use std::time::Duration;
-/// use smart_led_effects::strip::Strobe;
-///
-/// let count = 10;
-/// let colour = None;
-/// let period = Duration::from_secs(1);
-/// let decay = None;
+/// use smart_led_effects::{strip::{EffectIterator, Strobe}, RGB8};
 ///
-/// let mut effect = Strobe::new(count, colour, period, decay);
+/// let mut effect = Strobe::<10>::seeded(1, None, 1000, None);
+/// let mut pixels = [RGB8::default(); 10];
+/// effect.next_line(&mut pixels, 16);
 /// ```
 #[derive(Debug)]
-pub struct Strobe<const N: usize, R: RngCore> {
+pub struct Strobe<const N: usize, R: RngCore = FastRng> {
     colour: Option<Hsv>,
     current_colour: Hsv,
     period_ticks: u32,
     fade_val: f32,
-    elapsed_ticks: u32,
+    automatic: bool,
+    mode: StrobeMode,
+    burst: u32,
+    pause_ticks: u32,
+    segments: usize,
+    colours: Option<&'static [Srgb]>,
+    min_interval: u32,
+    /// Ticks into the current burst and pause.
+    clock: u32,
+    bursts: u32,
+    since_flash: u32,
     rng: R,
 }
 
@@ -46,37 +80,268 @@ impl<const N: usize, R: RngCore> Strobe<N, R> {
         Self {
             colour,
             current_colour,
-            period_ticks,
+            period_ticks: period_ticks.max(1),
             fade_val: decay.unwrap_or(0.02),
-            elapsed_ticks: 0,
+            automatic: true,
+            mode: StrobeMode::default(),
+            burst: 1,
+            pause_ticks: 0,
+            segments: 1,
+            colours: None,
+            min_interval: 0,
+            clock: 0,
+            bursts: 0,
+            since_flash: 0,
             rng,
         }
     }
 
+    /// Time between flashes, e.g. [crate::beat::BeatClock::beat_ticks]. This only follows the
+    /// tempo; to flash on the beat as well, call [Strobe::trigger] on each beat.
+    pub fn set_period(&mut self, period_ticks: u32) {
+        self.period_ticks = period_ticks.max(1);
+    }
+
+    /// Flash now, e.g. on a beat from [crate::audio::Analyser::take_beat].
+    pub fn trigger(&mut self) {
+        self.clock = 0;
+        self.flash();
+    }
+
+    /// When false the strobe only flashes on [Strobe::trigger]. True by default.
+    pub fn set_automatic(&mut self, automatic: bool) {
+        self.automatic = automatic;
+    }
+
+    pub fn set_mode(&mut self, mode: StrobeMode) {
+        self.mode = mode;
+    }
+
+    /// Flash `flashes` times, one period apart, then stay dark for `pause_ticks`.
+    /// One flash with no pause by default.
+    pub fn set_burst(&mut self, flashes: u32, pause_ticks: u32) {
+        self.burst = flashes.max(1);
+        self.pause_ticks = pause_ticks;
+    }
+
+    /// Split the strip into `segments` that take turns: even segments light on one burst, odd
+    /// ones on the next. 1 (the whole strip) by default.
+    pub fn set_segments(&mut self, segments: usize) {
+        self.segments = segments.max(1);
+    }
+
+    /// Colour of each segment in turn, instead of the strobe colour.
+    pub fn set_colours(&mut self, colours: Option<&'static [Srgb]>) {
+        self.colours = colours.filter(|c| !c.is_empty());
+    }
+
+    /// Never start flashes more often than `hz` times a second (with ticks in milliseconds),
+    /// skipping any that come too soon. Off by default; photosensitivity guidelines such as
+    /// WCAG recommend no more than 3.
+    pub fn set_max_frequency(&mut self, hz: Option<f32>) {
+        self.min_interval = hz.map_or(0, |hz| (1000.0 / hz.max(0.001)) as u32);
+    }
+
     fn genereate_colour(&mut self) {
-        // derive two floats in [0,1)
-        let h = (self.rng.next_u32() as f32) / (u32::MAX as f32 + 1.0);
-        let s = (self.rng.next_u32() as f32) / (u32::MAX as f32 + 1.0);
-        self.current_colour = Hsv::new(h * 360.0, s, 1.0);
+        self.current_colour = random_colour(&mut self.rng);
     }
 
-    fn fade(&mut self) -> bool {
-        self.current_colour.value -= self.fade_val;
-        if self.current_colour.value <= 0.0 {
-            self.current_colour.value = 0.0;
-            true
-        } else {
-            false
-        }
+    fn fade(&mut self) {
+        self.current_colour.value = (self.current_colour.value - self.fade_val).max(0.0);
     }
 
-    fn reset(&mut self) {
+    /// Start a flash, unless the last one was too recent.
+    fn flash(&mut self) {
+        if self.since_flash < self.min_interval {
+            return;
+        }
         match self.colour {
             Some(colour) => self.current_colour = colour,
             None => self.genereate_colour(),
         }
         self.current_colour.value = 1.0;
-        self.elapsed_ticks = 0;
+        self.since_flash = 0;
+    }
+
+    /// Run the burst schedule on by `dt_ticks`, flashing at the start of each period.
+    fn tick(&mut self, dt_ticks: u32) {
+        let cycle = self.period_ticks.saturating_mul(self.burst).saturating_add(self.pause_ticks);
+        let before = self.clock / self.period_ticks;
+        self.clock = self.clock.saturating_add(dt_ticks);
+        if self.clock >= cycle {
+            self.clock %= cycle.max(1);
+            self.bursts = self.bursts.wrapping_add(1);
+            self.flash();
+        } else {
+            let now = self.clock / self.period_ticks;
+            if now != before && now < self.burst {
+                self.flash();
+            }
+        }
+    }
+
+    /// Brightness of the current flash.
+    fn level(&self) -> f32 {
+        match self.mode {
+            StrobeMode::Decay => self.current_colour.value,
+            StrobeMode::Duty(duty) => {
+                let on = (duty.clamp(0.0, 1.0) * self.period_ticks as f32) as u32;
+                if self.current_colour.value > 0.0 && self.since_flash < on { 1.0 } else { 0.0 }
+            }
+            StrobeMode::Beacon => 1.0,
+        }
+    }
+
+    /// Colour of segment `segment` at full brightness.
+    fn segment_colour(&self, segment: usize) -> Hsv {
+        match self.colours {
+            Some(colours) => Hsv::from_color(colours[segment % colours.len()]),
+            None => self.current_colour,
+        }
+    }
+
+    /// Flash index and frame of the last flash by `time`, counting frames of `step_ticks`.
+    fn last_flash(&self, time: u64, step_ticks: u64) -> Option<(u64, u64)> {
+        if !self.automatic && self.mode != StrobeMode::Beacon {
+            return None;
+        }
+        let period = self.period_ticks as u64;
+        let burst = self.burst as u64;
+        let cycle = (period * burst + self.pause_ticks as u64).max(1);
+        let first = time / cycle / EPOCH * EPOCH;
+        // the strobe starts lit, and before that the last flash of the previous burst counts
+        let mut previous = match first {
+            0 => 0,
+            _ => (first * cycle - self.pause_ticks as u64 - period).div_ceil(step_ticks),
+        };
+        let mut last = None;
+        for bursts in first..=time / cycle {
+            for flash in 0..burst {
+                let at = bursts * cycle + flash * period;
+                if at > time {
+                    break;
+                }
+                let frame = at.div_ceil(step_ticks);
+                let index = bursts * burst + flash;
+                let spaced = (frame - previous) * step_ticks >= self.min_interval as u64;
+                if index > 0 && spaced {
+                    previous = frame;
+                    last = Some((index, frame));
+                }
+            }
+        }
+        last
+    }
+
+    /// Beacon brightness of pixel `i` out of `len`: a soft beam a quarter of the strip wide,
+    /// wrapping around the ends.
+    fn beam(&self, i: usize, len: usize) -> f32 {
+        let centre = self.clock as f32 / self.period_ticks as f32 * len as f32;
+        let offset = (i as f32 + 0.5 - centre).abs() % len as f32;
+        let distance = offset.min(len as f32 - offset);
+        let half_width = len as f32 / 8.0;
+        if distance < half_width {
+            0.5 + 0.5 * libm::cosf(PI * distance / half_width)
+        } else {
+            0.0
+        }
+    }
+}
+
+/// A random hue and saturation at full brightness.
+fn random_colour(rng: &mut impl RngCore) -> Hsv {
+    let h = rng.range_f32(0.0..360.0);
+    let s = rng.unit_f32();
+    Hsv::new(h, s, 1.0)
+}
+
+impl<const N: usize> Strobe<N> {
+    /// Same as [Strobe::new] using a [FastRng] seeded with `seed`.
+    pub fn seeded(seed: u64, colour: Option<Srgb<u8>>, period_ticks: u32, decay: Option<f32>) -> Self {
+        Self::new(FastRng::new(seed), colour, period_ticks, decay)
+    }
+
+    /// Police wig-wag: the two halves take turns with bursts of three red or blue flashes.
+    pub fn police() -> Self {
+        const COLOURS: &[Srgb] = &[Srgb::new(1.0, 0.0, 0.0), Srgb::new(0.0, 0.0, 1.0)];
+        let mut me = Self::seeded(0, None, 60, None);
+        me.set_mode(StrobeMode::Duty(0.5));
+        me.set_burst(3, 120);
+        me.set_segments(2);
+        me.set_colours(Some(COLOURS));
+        me
+    }
+
+    /// Amber beacon: a beam sweeping along the strip, once every 750 ticks.
+    pub fn amber_beacon() -> Self {
+        let mut me = Self::seeded(0, Some(Srgb::new(255, 120, 0)), 750, None);
+        me.set_mode(StrobeMode::Beacon);
+        me
+    }
+
+    /// Two quick flashes then a pause, in `colour` or white.
+    pub fn double_flash(colour: Option<Srgb<u8>>) -> Self {
+        let colour = colour.unwrap_or(Srgb::new(255, 255, 255));
+        let mut me = Self::seeded(0, Some(colour), 120, None);
+        me.set_mode(StrobeMode::Duty(0.4));
+        me.set_burst(2, 800);
+        me
+    }
+}
+
+impl<const N: usize, R: RngCore> PixelStream for Strobe<N, R> {
+    fn advance(&mut self, dt_ticks: u32) {
+        self.since_flash = self.since_flash.saturating_add(dt_ticks);
+        if self.mode == StrobeMode::Decay {
+            self.fade();
+        }
+        if self.automatic || self.mode == StrobeMode::Beacon {
+            self.tick(dt_ticks);
+        }
+    }
+
+    fn pixel<P: Pixel>(&self, i: usize) -> P {
+        let segment = i * self.segments / N;
+        let (mut colour, brightness) = if self.mode == StrobeMode::Beacon {
+            (self.segment_colour(0), self.beam(i, N))
+        } else if self.segments > 1 && segment % 2 != self.bursts as usize % 2 {
+            (self.current_colour, 0.0)
+        } else {
+            (self.segment_colour(segment), self.level())
+        };
+        colour.value = brightness;
+        P::from_hsv(colour)
+    }
+}
+
+impl<const N: usize, R: RngCore> Seek for Strobe<N, R> {
+    fn seek(&mut self, step: u64, step_ticks: u32, seed: u64) {
+        let step_ticks = step_ticks.max(1) as u64;
+        let time = step * step_ticks;
+        let cycle = (self.period_ticks as u64 * self.burst as u64 + self.pause_ticks as u64).max(1);
+        self.clock = (time % cycle) as u32;
+        self.bursts = (time / cycle) as u32;
+
+        let mut colour = match self.colour {
+            Some(colour) => colour,
+            None => Hsv::new(0.0, 0.0, 1.0),
+        };
+        let frame = match self.last_flash(time, step_ticks) {
+            Some((index, frame)) => {
+                if self.colour.is_none() {
+                    colour = random_colour(&mut FastRng::new(hash32(seed, index) as u64));
+                }
+                colour.value = 1.0;
+                frame
+            }
+            None => 0,
+        };
+        let frames = step - frame;
+        if self.mode == StrobeMode::Decay {
+            colour.value = (colour.value - self.fade_val * frames as f32).max(0.0);
+        }
+        self.current_colour = colour;
+        self.since_flash = (frames * step_ticks).min(u32::MAX as u64) as u32;
     }
 }
 
@@ -85,25 +350,18 @@ impl<const N: usize, R: RngCore> EffectIterator for Strobe<N, R> {
         "Strobe"
     }
 
-    fn next_line(&mut self, buf: &mut [crate::RGB8], dt_ticks: u32) -> Option<usize> {
-        if self.fade() {
-            self.elapsed_ticks = self.elapsed_ticks.saturating_add(dt_ticks);
-            if self.elapsed_ticks >= self.period_ticks {
-                self.reset();
-            }
-        }
-        let len = core::cmp::min(N, buf.len());
-        let px: Srgb<u8> = Srgb::from_color(self.current_colour).into_format();
-        let out = crate::RGB8 {
-            r: px.red,
-            g: px.green,
-            b: px.blue,
-        };
-        for slot in buf.iter_mut().take(len) { *slot = out; }
-        Some(len)
+    fn next_line(&mut self, buf: &mut [RGB8], dt_ticks: u32) -> Option<usize> {
+        self.next_line_as(buf, dt_ticks)
     }
 
     fn pixel_count(&self) -> usize {
         N
     }
 }
+
+impl<const N: usize, R: RngCore> RenderAs for Strobe<N, R> {
+    fn next_line_as<P: Pixel>(&mut self, buf: &mut [P], dt_ticks: u32) -> Option<usize> {
+        self.advance(dt_ticks);
+        Some(self.render(buf))
+    }
+}
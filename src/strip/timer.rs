@@ -1,6 +1,22 @@
-use crate::{strip::EffectIterator, RGB8};
+use crate::{strip::{EffectIterator, RenderAs}, Pixel, RGB8};
 use palette::{Mix, Srgb};
 
+/// What a [Timer] does once its time is up.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TimerEnd {
+    /// Keep showing the final frame until restarted.
+    Hold,
+    /// Flash the whole strip in the end colour until restarted or stopped.
+    Alarm,
+    /// Return `None` from [EffectIterator::next_line].
+    Finish,
+    /// Start counting again straight away.
+    Restart,
+}
+
+/// Shows the time left as a shrinking bar, or the time elapsed as a growing one.
+///
+/// Times are in ticks, the unit of `dt_ticks`. A timer of zero ticks is finished straight away.
 pub struct Timer<const N: usize> {
     total_ticks: u32,
     start_colour: Srgb,
@@ -9,11 +25,21 @@ pub struct Timer<const N: usize> {
     pixels_per_tick: f32,
     elapsed_ticks: u32,
     running: bool,
+    paused: bool,
+    count_up: bool,
+    end: TimerEnd,
+    warning_ticks: u32,
+    warning_colour: Option<Srgb>,
+    period_ticks: u32,
 }
 
 impl<const N: usize> Timer<N> {
     const DEFAULT_START_COLOUR: Srgb = Srgb::new(0.0, 0.0, 1.0);
     const DEFAULT_END_COLOUR: Srgb = Srgb::new(1.0, 0.0, 0.0);
+    const DEFAULT_PERIOD: u32 = 500;
+    /// Brightness at the bottom of a warning pulse.
+    const PULSE_FLOOR: f32 = 0.2;
+
     pub fn new(
         total_ticks: u32,
         start_colour: Option<Srgb>,
@@ -29,19 +55,140 @@ impl<const N: usize> Timer<N> {
             pixels_per_tick: N as f32 / total_ticks.max(1) as f32,
             elapsed_ticks: 0,
             running: start,
+            paused: false,
+            count_up: false,
+            end: TimerEnd::Restart,
+            warning_ticks: 0,
+            warning_colour: None,
+            period_ticks: Self::DEFAULT_PERIOD,
         }
     }
 
+    /// Start from zero.
     pub fn start(&mut self) {
         self.elapsed_ticks = 0;
         self.running = true;
+        self.paused = false;
     }
+
+    /// Stop and blank the strip.
     pub fn stop(&mut self) {
         self.running = false;
     }
+
     pub fn reset(&mut self) {
         self.elapsed_ticks = 0;
     }
+
+    /// Freeze the display until [Timer::resume].
+    pub fn pause(&mut self) {
+        self.paused = true;
+    }
+
+    pub fn resume(&mut self) {
+        self.paused = false;
+    }
+
+    pub fn is_running(&self) -> bool {
+        self.running && !self.paused
+    }
+
+    /// True once the time is up, until restarted.
+    pub fn is_finished(&self) -> bool {
+        self.elapsed_ticks >= self.total_ticks
+    }
+
+    pub fn elapsed_ticks(&self) -> u32 {
+        self.elapsed_ticks.min(self.total_ticks)
+    }
+
+    /// Ticks past the end, kept until restarted with [TimerEnd::Alarm] and [TimerEnd::Finish].
+    pub fn overrun_ticks(&self) -> u32 {
+        self.elapsed_ticks.saturating_sub(self.total_ticks)
+    }
+
+    pub fn remaining_ticks(&self) -> u32 {
+        self.total_ticks.saturating_sub(self.elapsed_ticks)
+    }
+
+    /// Change the duration, keeping the time already elapsed.
+    pub fn set_total_ticks(&mut self, total_ticks: u32) {
+        self.total_ticks = total_ticks;
+        self.pixels_per_tick = N as f32 / total_ticks.max(1) as f32;
+    }
+
+    pub fn set_colours(&mut self, start_colour: Srgb, end_colour: Srgb) {
+        self.start_colour = start_colour;
+        self.end_colour = end_colour;
+    }
+
+    pub fn set_gradient(&mut self, gradient: bool) {
+        self.gradient = gradient;
+    }
+
+    /// Defaults to [TimerEnd::Restart].
+    pub fn set_end(&mut self, end: TimerEnd) {
+        self.end = end;
+    }
+
+    /// Grow the bar from empty as time passes (stopwatch) instead of shrinking it.
+    pub fn set_count_up(&mut self, count_up: bool) {
+        self.count_up = count_up;
+    }
+
+    /// Pulse the bar during the last `ticks` ticks, in `colour` if given. `0` disables.
+    pub fn set_warning(&mut self, ticks: u32, colour: Option<Srgb>) {
+        self.warning_ticks = ticks;
+        self.warning_colour = colour;
+    }
+
+    /// Period of the warning pulse and the alarm flash. Defaults to 500 ticks.
+    pub fn set_period(&mut self, ticks: u32) {
+        self.period_ticks = ticks.max(2);
+    }
+
+    /// Triangle wave from 0 to 1 and back over one period.
+    fn triangle(&self, ticks: u32) -> f32 {
+        let half = self.period_ticks / 2;
+        let t = ticks % self.period_ticks;
+        if t < half {
+            t as f32 / half as f32
+        } else {
+            (self.period_ticks - t) as f32 / half as f32
+        }
+    }
+
+    fn render_bar<P: Pixel>(&self, buf: &mut [P]) {
+        let elapsed = self.elapsed_ticks.min(self.total_ticks) as f32;
+        let progressed = match self.total_ticks {
+            0 => N,
+            _ => ((self.pixels_per_tick * elapsed) as usize).min(N),
+        };
+        let pixels = if self.count_up { progressed } else { N - progressed };
+
+        let warning = self.warning_ticks > 0 && self.remaining_ticks() <= self.warning_ticks;
+        let brightness = if warning {
+            Self::PULSE_FLOOR + (1.0 - Self::PULSE_FLOOR) * self.triangle(self.elapsed_ticks)
+        } else {
+            1.0
+        };
+        let warning_colour = self.warning_colour.filter(|_| warning);
+
+        let count = core::cmp::min(pixels, buf.len());
+        if let Some(colour) = warning_colour {
+            let px = P::from_srgb(colour * brightness);
+            for slot in buf.iter_mut().take(count) { *slot = px; }
+        } else if self.gradient {
+            for (i, slot) in buf.iter_mut().enumerate().take(count) {
+                let colour = self.end_colour.mix(self.start_colour, i as f32 / N as f32);
+                *slot = P::from_srgb(colour * brightness);
+            }
+        } else {
+            let mix = if self.total_ticks == 0 { 1.0 } else { elapsed / self.total_ticks as f32 };
+            let px = P::from_srgb(self.start_colour.mix(self.end_colour, mix) * brightness);
+            for slot in buf.iter_mut().take(count) { *slot = px; }
+        }
+    }
 }
 
 impl<const N: usize> EffectIterator for Timer<N> {
@@ -50,45 +197,41 @@ impl<const N: usize> EffectIterator for Timer<N> {
     }
 
     fn next_line(&mut self, buf: &mut [RGB8], dt_ticks: u32) -> Option<usize> {
+        self.next_line_as(buf, dt_ticks)
+    }
+
+    fn pixel_count(&self) -> usize {
+        N
+    }
+}
+
+impl<const N: usize> RenderAs for Timer<N> {
+    fn next_line_as<P: Pixel>(&mut self, buf: &mut [P], dt_ticks: u32) -> Option<usize> {
         let len = core::cmp::min(N, buf.len());
-        for slot in buf.iter_mut().take(len) { *slot = RGB8 { r:0, g:0, b:0 }; }
+        let buf = &mut buf[..len];
+        for slot in buf.iter_mut() { *slot = P::BLACK; }
         if !self.running {
             return Some(len);
         }
-        self.elapsed_ticks = self.elapsed_ticks.saturating_add(dt_ticks);
-        if self.elapsed_ticks >= self.total_ticks {
-            self.reset();
-            return Some(len);
+        if !self.paused {
+            self.elapsed_ticks = self.elapsed_ticks.saturating_add(dt_ticks);
         }
-        let elapsed = self.elapsed_ticks as f32;
-        let progressed = (self.pixels_per_tick * elapsed) as usize;
-        let pixels = N.saturating_sub(progressed);
-        if self.gradient {
-            for (i, slot) in buf.iter_mut().enumerate().take(core::cmp::min(pixels, len)) {
-                let p: Srgb<u8> = self
-                    .end_colour
-                    .mix(self.start_colour, i as f32 / N as f32)
-                    .into_format();
-                *slot = RGB8 {
-                    r: p.red,
-                    g: p.green,
-                    b: p.blue,
-                };
+        if self.is_finished() {
+            match self.end {
+                TimerEnd::Hold => self.elapsed_ticks = self.total_ticks,
+                TimerEnd::Alarm => {
+                    let since = self.elapsed_ticks - self.total_ticks;
+                    if since % self.period_ticks < self.period_ticks / 2 {
+                        let px = P::from_srgb(self.end_colour);
+                        for slot in buf.iter_mut() { *slot = px; }
+                    }
+                    return Some(len);
+                }
+                TimerEnd::Finish => return None,
+                TimerEnd::Restart => self.elapsed_ticks %= self.total_ticks.max(1),
             }
-        } else {
-            let mix = elapsed / self.total_ticks as f32;
-            let p: Srgb<u8> = self.start_colour.mix(self.end_colour, mix).into_format();
-            let px = RGB8 {
-                r: p.red,
-                g: p.green,
-                b: p.blue,
-            };
-            for slot in buf.iter_mut().take(core::cmp::min(pixels, len)) { *slot = px; }
         }
+        self.render_bar(buf);
         Some(len)
     }
-
-    fn pixel_count(&self) -> usize {
-        N
-    }
 }
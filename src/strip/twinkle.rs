@@ -1,8 +1,19 @@
-use crate::{strip::EffectIterator, RGB8};
-use palette::{FromColor, Hsv, Srgb};
+use crate::{
+    rng::{hash32, FastRng, RandomExt},
+    strip::{EffectIterator, PixelStream, RenderAs, Seek},
+    Pixel, RGB8,
+};
+use palette::{FromColor, Hsv, Mix, Srgb};
 use rand_core::RngCore;
 
-pub struct Twinkle<const N: usize, R: RngCore> {
+/// Most frames [Seek] replays to rebuild the sparkles still fading.
+const MAX_REPLAY: u64 = 256;
+
+/// Random sparkles that appear and fade out.
+///
+/// With [Seek] the sparkles of each frame come from the seed and the frame number, and the
+/// frames a sparkle takes to fade are replayed, at most 256 of them.
+pub struct Twinkle<const N: usize, R: RngCore = FastRng> {
     frequency: u8,
     probability: f32,
     fade: f32,
@@ -42,20 +53,25 @@ impl<const N: usize, R: RngCore> Twinkle<N, R> {
 
     pub fn white(rng: R) -> Self { Self::sparkle(rng, Some(Srgb::<u8>::new(255, 255, 255))) }
 
-    fn generate_sparkle(&mut self) {
-        let index = (self.rng.next_u32() as usize) % N;
-        let mut sparkle = match self.colour {
-            Some(colour) => colour,
-            None => {
-                let hue = (self.rng.next_u32() as f32) / (u32::MAX as f32 + 1.0) * 360.0;
-                Hsv::new(hue, 1.0, 0.0)
-            }
+    /// Fade the sparkles and add this frame's new ones, drawn from `replay` when seeking.
+    fn frame(&mut self, replay: Option<&mut FastRng>) {
+        self.fade_sparkles();
+        let rng: &mut dyn RngCore = match replay {
+            Some(rng) => rng,
+            None => &mut self.rng,
         };
-        let v = 0.5 + (self.rng.next_u32() as f32) / (u32::MAX as f32 + 1.0) * 0.5;
-        sparkle.value = v;
-        let chance = (self.rng.next_u32() as f32) / (u32::MAX as f32 + 1.0);
-        if chance < self.probability {
-            self.current[index] = sparkle;
+        // 0..frequency random sparkles per frame
+        let chances = (rng.next_u32() % self.frequency.max(1) as u32) as u8;
+        for _ in 0..chances {
+            let index = rng.index(N);
+            let mut sparkle = match self.colour {
+                Some(colour) => colour,
+                None => Hsv::new(rng.range_f32(0.0..360.0), 1.0, 0.0),
+            };
+            sparkle.value = rng.range_f32(0.5..1.0);
+            if rng.chance(self.probability) {
+                self.current[index] = sparkle;
+            }
         }
     }
 
@@ -70,26 +86,196 @@ impl<const N: usize, R: RngCore> Twinkle<N, R> {
     }
 }
 
+impl<const N: usize> Twinkle<N> {
+    /// Same as [Twinkle::new] using a [FastRng] seeded with `seed`.
+    pub fn seeded(
+        seed: u64,
+        colour: Option<Srgb<u8>>,
+        sparkle: Option<u8>,
+        probability: Option<f32>,
+        fade: Option<f32>,
+    ) -> Self {
+        Self::new(FastRng::new(seed), colour, sparkle, probability, fade)
+    }
+}
+
+impl<const N: usize, R: RngCore> PixelStream for Twinkle<N, R> {
+    fn advance(&mut self, _dt: u32) {
+        self.frame(None);
+    }
+
+    fn pixel<P: Pixel>(&self, index: usize) -> P {
+        P::from_hsv(self.current[index])
+    }
+}
+
+impl<const N: usize, R: RngCore> Seek for Twinkle<N, R> {
+    fn seek(&mut self, step: u64, _step_ticks: u32, seed: u64) {
+        // a sparkle has faded out 1 / fade frames after it appeared
+        let frames = ((1.0 / self.fade) as u64).saturating_add(1).min(MAX_REPLAY);
+        self.current = [Hsv::new(0.0, 1.0, 0.0); N];
+        for frame in step.saturating_sub(frames) + 1..=step {
+            self.frame(Some(&mut FastRng::new(hash32(seed, frame) as u64)));
+        }
+    }
+}
+
 impl<const N: usize, R: RngCore> EffectIterator for Twinkle<N, R> {
     fn name(&self) -> &'static str {
         "Twinkle"
     }
 
-    fn next_line(&mut self, buf: &mut [RGB8], _dt: u32) -> Option<usize> {
-        self.fade_sparkles();
-        let chances = (self.rng.next_u32() % self.frequency as u32) as u8;
-        for _ in 0..chances {
-            self.generate_sparkle();
+    fn next_line(&mut self, buf: &mut [RGB8], dt: u32) -> Option<usize> {
+        self.next_line_as(buf, dt)
+    }
+
+    fn pixel_count(&self) -> usize {
+        N
+    }
+}
+
+impl<const N: usize, R: RngCore> RenderAs for Twinkle<N, R> {
+    fn next_line_as<P: Pixel>(&mut self, buf: &mut [P], dt: u32) -> Option<usize> {
+        self.advance(dt);
+        Some(self.render(buf))
+    }
+}
+
+/// TwinkleFox style twinkling: every pixel brightens and dims on its own clock.
+///
+/// Each pixel's phase, speed and colour are derived from the seed and its index, so no
+/// per-pixel state is kept and the effect can be streamed, see [PixelStream]. Twinkles rise
+/// quickly and fade slowly, optionally cooling towards red like an incandescent bulb, over an
+/// optional background colour. Times are in ticks.
+///
+/// [Seek] only sets the time; the twinkles still come from the seed given to [TwinkleFox::new].
+pub struct TwinkleFox<const N: usize> {
+    seed: u64,
+    time: u64,
+    palette: &'static [Srgb],
+    background: Srgb,
+    period: u32,
+    density: f32,
+    attack: f32,
+    cool: bool,
+}
+
+impl<const N: usize> TwinkleFox<N> {
+    /// Warm white, like incandescent fairy lights.
+    pub const WARM_WHITE: &'static [Srgb] = &[Srgb::new(1.0, 0.55, 0.18)];
+    const DEFAULT_PERIOD: u32 = 2000;
+    const DEFAULT_DENSITY: f32 = 0.6;
+    const DEFAULT_ATTACK: f32 = 0.3;
+
+    /// `palette` defaults to [TwinkleFox::WARM_WHITE].
+    pub fn new(seed: u64, palette: Option<&'static [Srgb]>) -> Self {
+        Self {
+            seed,
+            time: 0,
+            palette: palette.unwrap_or(Self::WARM_WHITE),
+            background: Srgb::new(0.0, 0.0, 0.0),
+            period: Self::DEFAULT_PERIOD,
+            density: Self::DEFAULT_DENSITY,
+            attack: Self::DEFAULT_ATTACK,
+            cool: true,
         }
-        let len = core::cmp::min(N, buf.len());
-        for (i, slot) in buf.iter_mut().enumerate().take(len) {
-            let p: Srgb<u8> = Srgb::from_color(self.current[i]).into_format();
-            *slot = RGB8 { r: p.red, g: p.green, b: p.blue };
+    }
+
+    /// Colours picked at random for each twinkle.
+    pub fn set_palette(&mut self, palette: &'static [Srgb]) {
+        self.palette = palette;
+    }
+
+    /// Colour shown between twinkles, black by default.
+    pub fn set_background(&mut self, background: Option<Srgb>) {
+        self.background = background.unwrap_or(Srgb::new(0.0, 0.0, 0.0));
+    }
+
+    /// Average length of a twinkle, 2000 by default. Each pixel runs 0.5 to 1.5 times this.
+    pub fn set_period(&mut self, ticks: u32) {
+        self.period = ticks.max(1);
+    }
+
+    /// Fraction of twinkles that light up, 0.6 by default.
+    pub fn set_density(&mut self, density: f32) {
+        self.density = density.clamp(0.0, 1.0);
+    }
+
+    /// Fraction of each twinkle spent brightening, 0.3 by default.
+    pub fn set_attack(&mut self, attack: f32) {
+        self.attack = attack.clamp(0.01, 0.99);
+    }
+
+    /// Whether fading twinkles shift towards red, on by default.
+    pub fn set_cool(&mut self, cool: bool) {
+        self.cool = cool;
+    }
+
+    fn srgb_at(&self, index: usize) -> Srgb {
+        let hash = hash32(self.seed, index as u64);
+        // in 1/65536ths of a twinkle, integer so the phase stays accurate however long it runs
+        let speed = 128 + (hash & 0xff) as u64; /* 0.5 to 1.5 in 1/256ths */
+        let offset = (hash >> 8 & 0xffff) as u64;
+        let turns = self.time * speed * 256 / self.period as u64 + offset;
+        let cycle = turns >> 16;
+        let phase = (turns & 0xffff) as f32 / 65536.0;
+
+        // a fresh draw for every twinkle decides whether it lights and its colour
+        let twinkle = hash32(self.seed ^ hash as u64, cycle);
+        if ((twinkle & 0xffff) as f32 / 65536.0) >= self.density || self.palette.is_empty() {
+            return self.background;
         }
-        Some(len)
+        let mut colour = self.palette[(twinkle >> 16) as usize % self.palette.len()];
+
+        let brightness = if phase < self.attack {
+            // smoothstep up
+            let x = phase / self.attack;
+            x * x * (3.0 - 2.0 * x)
+        } else {
+            let fading = (phase - self.attack) / (1.0 - self.attack);
+            if self.cool {
+                colour.green *= 1.0 - 0.5 * fading;
+                colour.blue *= 1.0 - fading;
+            }
+            (1.0 - fading) * (1.0 - fading)
+        };
+        self.background.mix(colour, brightness)
+    }
+}
+
+impl<const N: usize> PixelStream for TwinkleFox<N> {
+    fn advance(&mut self, dt: u32) {
+        self.time += dt as u64;
+    }
+
+    fn pixel<P: Pixel>(&self, index: usize) -> P {
+        P::from_srgb(self.srgb_at(index))
+    }
+}
+
+impl<const N: usize> Seek for TwinkleFox<N> {
+    fn seek(&mut self, step: u64, step_ticks: u32, _seed: u64) {
+        self.time = step * step_ticks as u64;
+    }
+}
+
+impl<const N: usize> EffectIterator for TwinkleFox<N> {
+    fn name(&self) -> &'static str {
+        "TwinkleFox"
+    }
+
+    fn next_line(&mut self, buf: &mut [RGB8], dt: u32) -> Option<usize> {
+        self.next_line_as(buf, dt)
     }
 
     fn pixel_count(&self) -> usize {
         N
     }
 }
+
+impl<const N: usize> RenderAs for TwinkleFox<N> {
+    fn next_line_as<P: Pixel>(&mut self, buf: &mut [P], dt: u32) -> Option<usize> {
+        self.advance(dt);
+        Some(self.render(buf))
+    }
+}
@@ -0,0 +1,145 @@
+use crate::{
+    strip::{bar_graph::render_bar, pace::approach, EffectIterator, RenderAs, Zones},
+    Pixel, RGB8,
+};
+use palette::Srgb;
+
+/// How the meter is laid out on the strip.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum VuLayout {
+    /// One bar rising from index 0, showing the left channel.
+    Mono,
+    /// Left channel grows from the centre towards index 0, right towards the end.
+    Stereo,
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+struct Channel {
+    target: f32,
+    level: f32,
+    peak: f32,
+    peak_age: u32,
+}
+
+impl Channel {
+    fn update(&mut self, dt: u32, attack: u32, decay: u32, hold: u32) {
+        let tau = if self.target > self.level { attack } else { decay };
+        self.level += (self.target - self.level) * approach(dt, tau);
+
+        if self.level >= self.peak {
+            self.peak = self.level;
+            self.peak_age = 0;
+        } else {
+            self.peak_age = self.peak_age.saturating_add(dt);
+            if self.peak_age > hold {
+                self.peak += (self.level - self.peak) * approach(dt, decay);
+            }
+        }
+    }
+}
+
+/// Audio level meter with attack/decay ballistics and peak-hold markers.
+///
+/// Feed it levels with [VuMeter::set_percentage] or [VuMeter::set_stereo] as often as you
+/// have them; the display follows with the configured attack and decay time constants, in
+/// ticks. Colours come from [Zones].
+pub struct VuMeter<const N: usize> {
+    layout: VuLayout,
+    zones: Zones,
+    peak_colour: Option<Srgb>,
+    attack_ticks: u32,
+    decay_ticks: u32,
+    hold_ticks: u32,
+    channels: [Channel; 2],
+}
+
+impl<const N: usize> VuMeter<N> {
+    const DEFAULT_ATTACK: u32 = 10;
+    const DEFAULT_DECAY: u32 = 300;
+    const DEFAULT_HOLD: u32 = 1000;
+    const DEFAULT_PEAK_COLOUR: Srgb = Srgb::new(1.0, 1.0, 1.0);
+
+    /// `zones` defaults to green/yellow/red. Attack, decay and hold default to 10, 300 and
+    /// 1000 ticks (milliseconds if `dt_ticks` is in ms).
+    pub fn new(layout: VuLayout, zones: Option<Zones>) -> Self {
+        Self {
+            layout,
+            zones: zones.unwrap_or_default(),
+            peak_colour: Some(Self::DEFAULT_PEAK_COLOUR),
+            attack_ticks: Self::DEFAULT_ATTACK,
+            decay_ticks: Self::DEFAULT_DECAY,
+            hold_ticks: Self::DEFAULT_HOLD,
+            channels: [Channel::default(); 2],
+        }
+    }
+
+    /// Set both channels to `percentage` (0 to 100).
+    pub fn set_percentage(&mut self, percentage: f32) {
+        self.set_stereo(percentage, percentage);
+    }
+
+    /// Set the left and right channels, as percentages.
+    pub fn set_stereo(&mut self, left: f32, right: f32) {
+        self.channels[0].target = left.clamp(0.0, 100.0) / 100.0;
+        self.channels[1].target = right.clamp(0.0, 100.0) / 100.0;
+    }
+
+    /// Time constants for the level rising and falling, in ticks.
+    pub fn set_ballistics(&mut self, attack_ticks: u32, decay_ticks: u32) {
+        self.attack_ticks = attack_ticks;
+        self.decay_ticks = decay_ticks;
+    }
+
+    /// How long peaks are held before falling, and their colour. None hides the markers.
+    pub fn set_peak_hold(&mut self, hold_ticks: u32, colour: Option<Srgb>) {
+        self.hold_ticks = hold_ticks;
+        self.peak_colour = colour;
+    }
+
+    fn render_channel<P: Pixel>(&self, segment: &mut [P], channel: &Channel, reverse: bool) {
+        let zones = self.zones;
+        render_bar(segment, channel.level, reverse, |f| zones.colour(f));
+        if let Some(colour) = self.peak_colour {
+            let count = segment.len();
+            if count == 0 || channel.peak <= 0.0 {
+                return;
+            }
+            let index = ((channel.peak * count as f32) as usize).min(count - 1);
+            let index = if reverse { count - 1 - index } else { index };
+            segment[index] = P::from_srgb(colour);
+        }
+    }
+}
+
+impl<const N: usize> EffectIterator for VuMeter<N> {
+    fn name(&self) -> &'static str {
+        "VuMeter"
+    }
+
+    fn next_line(&mut self, buf: &mut [RGB8], dt_ticks: u32) -> Option<usize> {
+        self.next_line_as(buf, dt_ticks)
+    }
+
+    fn pixel_count(&self) -> usize {
+        N
+    }
+}
+
+impl<const N: usize> RenderAs for VuMeter<N> {
+    fn next_line_as<P: Pixel>(&mut self, buf: &mut [P], dt_ticks: u32) -> Option<usize> {
+        for channel in self.channels.iter_mut() {
+            channel.update(dt_ticks, self.attack_ticks, self.decay_ticks, self.hold_ticks);
+        }
+        let len = core::cmp::min(N, buf.len());
+        let buf = &mut buf[..len];
+        match self.layout {
+            VuLayout::Mono => self.render_channel(buf, &self.channels[0], false),
+            VuLayout::Stereo => {
+                let (left, right) = buf.split_at_mut(len / 2);
+                self.render_channel(left, &self.channels[0], true);
+                self.render_channel(right, &self.channels[1], false);
+            }
+        }
+        Some(len)
+    }
+}
@@ -1,8 +1,22 @@
-use crate::{strip::EffectIterator, RGB8};
+use crate::{
+    math8::{ease16_in_out_sine, lerp8by8},
+    rng::{FastRng, RandomExt},
+    strip::{pace::Pace, EffectIterator, Fill, RenderAs},
+    Pixel, RGB8,
+};
 use palette::{FromColor, Hsv, Srgb};
 use rand_core::RngCore;
 
-pub struct Wipe<'a, const N: usize, R: RngCore> {
+/// Sub-steps per pixel, so paced colour wipes move smoothly.
+const SUBSTEPS: usize = 256;
+
+/// Wipes `data` or colours along the strip.
+///
+/// [Wipe::new] slides `data` in and then blanks. [Wipe::colour_wipe] fills the strip with a
+/// colour and then unfills it, and [Wipe::sequence] wipes each colour of a list over the one
+/// before. Colour wipes can run in any [Fill] direction, shade towards a gradient colour and
+/// ease in and out over the duration set with [Wipe::set_duration].
+pub struct Wipe<'a, const N: usize, R: RngCore = FastRng> {
     position: usize,
     data: &'a [RGB8],
     reverse: bool,
@@ -10,7 +24,16 @@ pub struct Wipe<'a, const N: usize, R: RngCore> {
     randomize: bool,
     rng: R,
     colour_mode: bool,
-    fill_colour: Option<RGB8>,
+    colour: RGB8,
+    previous: Option<RGB8>,
+    sequence: &'a [RGB8],
+    next: usize,
+    fill: Fill,
+    unfill: bool,
+    unfilling: bool,
+    eased: bool,
+    gradient: Option<RGB8>,
+    pace: Pace,
 }
 
 impl<'a, const N: usize, R: RngCore> Wipe<'a, N, R> {
@@ -24,36 +47,223 @@ impl<'a, const N: usize, R: RngCore> Wipe<'a, N, R> {
             randomize: false,
             rng,
             colour_mode: false,
-            fill_colour: None,
+            colour: RGB8::default(),
+            previous: None,
+            sequence: &[],
+            next: 0,
+            fill: if reverse { Fill::Forward } else { Fill::Reverse },
+            unfill: true,
+            unfilling: false,
+            eased: false,
+            gradient: None,
+            pace: Pace::default(),
         }
     }
 
+    /// Fill with `colour`, or a new random colour each time when None, then unfill.
     pub fn colour_wipe(rng: R, colour: Option<RGB8>, reverse: bool) -> Self {
         let mut me = Self::new(rng, &[], reverse);
+        me.colour_mode = true;
+        me.position = 0;
         match colour {
-            Some(c) => me.fill_wipe(c),
+            Some(c) => me.colour = c,
             None => me.randomize_colour_wipe(),
         }
         me
     }
 
-    fn fill_wipe(&mut self, colour: RGB8) {
-        // use an internal single-colour mode: write zeros + colour + zeros based on indices
-        self.colour_mode = true;
-        self.fill_colour = Some(colour);
+    /// Wipe each of `colours` in turn over the previous one, starting from black, or random
+    /// colours when `colours` is empty.
+    pub fn sequence(rng: R, colours: &'a [RGB8], fill: Fill) -> Self {
+        let mut me = Self::colour_wipe(rng, colours.first().copied(), false);
+        me.sequence = colours;
+        me.next = 1 % colours.len().max(1);
+        me.fill = fill;
+        me.unfill = false;
+        me
+    }
+
+    /// Time for one complete wipe, e.g. [crate::beat::BeatClock::bar_ticks].
+    /// None (the default) moves one pixel per frame. This only follows the tempo; use
+    /// [Wipe::set_phase] to line the wipe up with the beat.
+    pub fn set_duration(&mut self, ticks: Option<u32>) {
+        self.pace.set_ticks(ticks);
+    }
+
+    /// Jump to `phase` (0 to 1) of the way through the current wipe, counting filling and
+    /// unfilling together, e.g. `wipe.set_phase(clock.bar_phase(now))` with a
+    /// [crate::beat::BeatClock].
+    pub fn set_phase(&mut self, phase: f32) {
+        let phase = phase.clamp(0.0, 1.0);
+        if !self.colour_mode {
+            let along = ((phase * self.end as f32) as usize).min(self.end.saturating_sub(1));
+            self.position = if self.reverse { self.end - along } else { along };
+            return;
+        }
+        let len = self.extent().max(1) * SUBSTEPS;
+        let phases = if self.unfill { 2 } else { 1 };
+        let along = ((phase * (len * phases) as f32) as usize).min(len * phases - 1);
+        self.unfilling = along >= len;
+        self.position = along % len;
+    }
+
+    /// Direction colour wipes fill in.
+    pub fn set_fill(&mut self, fill: Fill) {
+        self.fill = fill;
+    }
+
+    /// Whether colour wipes go back to black after filling, the same way they filled. On for
+    /// [Wipe::colour_wipe], off for [Wipe::sequence].
+    pub fn set_unfill(&mut self, unfill: bool) {
+        self.unfill = unfill;
+        self.unfilling &= unfill;
+    }
+
+    /// Start and finish each colour wipe slowly, off by default.
+    pub fn set_eased(&mut self, eased: bool) {
+        self.eased = eased;
+    }
+
+    /// Shade colour wipes from their colour where the wipe starts to `colour` where it ends.
+    pub fn set_gradient(&mut self, colour: Option<RGB8>) {
+        self.gradient = colour;
+    }
+
+    fn step(&mut self) {
+        if self.reverse {
+            if self.position == 0 {
+                self.position = self.end;
+            } else {
+                self.position -= 1;
+            }
+        } else {
+            self.position += 1;
+            if self.position >= self.end {
+                self.position = 0;
+            }
+        }
     }
 
     fn randomize_colour_wipe(&mut self) {
         // generate random colour
-        let h = (self.rng.next_u32() as f32) / (u32::MAX as f32 + 1.0) * 360.0;
+        let h = self.rng.range_f32(0.0..360.0);
         let srgb8: Srgb<u8> = Srgb::from_color(Hsv::new(h, 1.0, 1.0)).into_format();
-        self.fill_wipe(RGB8 {
+        self.colour = RGB8 {
             r: srgb8.red,
             g: srgb8.green,
             b: srgb8.blue,
-        });
+        };
         self.randomize = true;
     }
+
+    /// Pixels a colour wipe travels in its direction.
+    fn extent(&self) -> usize {
+        match self.fill {
+            Fill::Forward | Fill::Reverse => N,
+            Fill::CentreOut | Fill::EdgesIn => N.div_ceil(2),
+        }
+    }
+
+    /// Position of pixel `i` along the fill direction.
+    fn fill_position(&self, i: usize) -> usize {
+        match self.fill {
+            Fill::Forward => i,
+            Fill::Reverse => N - 1 - i,
+            Fill::CentreOut if i >= N / 2 => i - N / 2,
+            Fill::CentreOut => (N - 1) / 2 - i,
+            Fill::EdgesIn => i.min(N - 1 - i),
+        }
+    }
+
+    /// Move on to the next colour wipe, or start unfilling.
+    fn finish(&mut self) {
+        if self.unfill && !self.unfilling {
+            self.unfilling = true;
+            return;
+        }
+        self.previous = if self.unfilling { None } else { Some(self.colour) };
+        self.unfilling = false;
+        if !self.sequence.is_empty() {
+            self.colour = self.sequence[self.next];
+            self.next = (self.next + 1) % self.sequence.len();
+        } else if self.randomize {
+            self.randomize_colour_wipe();
+        }
+    }
+
+    fn advance_colour(&mut self, dt: u32) {
+        let len = self.extent().max(1) * SUBSTEPS;
+        // the duration covers filling and unfilling
+        let phases = if self.unfill { 2 } else { 1 };
+        self.position += if self.pace.is_paced() {
+            self.pace.steps(dt, len * phases)
+        } else {
+            SUBSTEPS
+        };
+        while self.position >= len {
+            self.position -= len;
+            self.finish();
+        }
+    }
+
+    /// `colour` at `position` along the wipe, shaded towards the gradient, or black for None.
+    fn shade(&self, colour: Option<RGB8>, position: usize, extent: usize) -> RGB8 {
+        let Some(colour) = colour else {
+            return RGB8::default();
+        };
+        let Some(end) = self.gradient else {
+            return colour;
+        };
+        let frac = (position * 255 / extent.saturating_sub(1).max(1)) as u8;
+        RGB8::new(
+            lerp8by8(colour.r, end.r, frac),
+            lerp8by8(colour.g, end.g, frac),
+            lerp8by8(colour.b, end.b, frac),
+        )
+    }
+
+    fn colour_pixel(&self, i: usize) -> RGB8 {
+        let extent = self.extent().max(1);
+        let len = extent * SUBSTEPS;
+        let mut fraction = (self.position * 65536 / len) as u16;
+        if self.eased {
+            fraction = ease16_in_out_sine(fraction);
+        }
+        // leading edge in 1/256ths of a pixel
+        let front = fraction as usize * len / 65536;
+        let position = self.fill_position(i);
+        let (under, over) = if self.unfilling {
+            (Some(self.colour), None)
+        } else {
+            (self.previous, Some(self.colour))
+        };
+        let covered = front.saturating_sub(position * SUBSTEPS);
+        if covered >= SUBSTEPS - 1 {
+            self.shade(over, position, extent)
+        } else {
+            let (under, over) = (self.shade(under, position, extent), self.shade(over, position, extent));
+            let frac = covered as u8;
+            RGB8::new(
+                lerp8by8(under.r, over.r, frac),
+                lerp8by8(under.g, over.g, frac),
+                lerp8by8(under.b, over.b, frac),
+            )
+        }
+    }
+}
+
+impl<const N: usize> Wipe<'_, N> {
+    /// Same as [Wipe::colour_wipe] using a [FastRng] seeded with `seed`.
+    pub fn seeded_colour_wipe(seed: u64, colour: Option<RGB8>, reverse: bool) -> Self {
+        Self::colour_wipe(FastRng::new(seed), colour, reverse)
+    }
+}
+
+impl<'a, const N: usize> Wipe<'a, N> {
+    /// Same as [Wipe::sequence] using a [FastRng] seeded with `seed`.
+    pub fn seeded_sequence(seed: u64, colours: &'a [RGB8], fill: Fill) -> Self {
+        Self::sequence(FastRng::new(seed), colours, fill)
+    }
 }
 
 impl<'a, const N: usize, R: RngCore> EffectIterator for Wipe<'a, N, R> {
@@ -61,47 +271,39 @@ impl<'a, const N: usize, R: RngCore> EffectIterator for Wipe<'a, N, R> {
         "Wipe"
     }
 
-    fn next_line(&mut self, buf: &mut [RGB8], _dt: u32) -> Option<usize> {
-        let used_len = if self.colour_mode { N } else { self.data.len() };
-        self.end = N + used_len;
-        let pos = self.position;
+    fn next_line(&mut self, buf: &mut [RGB8], dt: u32) -> Option<usize> {
+        self.next_line_as(buf, dt)
+    }
+
+    fn pixel_count(&self) -> usize {
+        N
+    }
+}
+
+impl<'a, const N: usize, R: RngCore> RenderAs for Wipe<'a, N, R> {
+    fn next_line_as<P: Pixel>(&mut self, buf: &mut [P], dt: u32) -> Option<usize> {
         let len = core::cmp::min(N, buf.len());
+        if self.colour_mode {
+            for (i, slot) in buf.iter_mut().enumerate().take(len) {
+                *slot = P::from_rgb8(self.colour_pixel(i));
+            }
+            self.advance_colour(dt);
+            return Some(len);
+        }
+
+        self.end = N + self.data.len();
+        let pos = self.position;
         for (i, slot) in buf.iter_mut().enumerate().take(len) {
             let j = pos + i;
-            *slot = if j < N {
-                RGB8 { r: 0, g: 0, b: 0 }
-            } else if j < N + used_len {
-                if self.colour_mode {
-                    self.fill_colour.unwrap_or(RGB8 { r: 0, g: 0, b: 0 })
-                } else {
-                    self.data[j - N]
-                }
+            *slot = if j >= N && j < self.end {
+                P::from_rgb8(self.data[j - N])
             } else {
-                RGB8 { r: 0, g: 0, b: 0 }
+                P::BLACK
             };
         }
-        if self.reverse {
-            if self.position == 0 {
-                self.position = self.end;
-                if self.randomize {
-                    self.randomize_colour_wipe();
-                }
-            } else {
-                self.position -= 1;
-            }
-        } else {
-            self.position += 1;
-            if self.position >= self.end {
-                self.position = 0;
-                if self.randomize {
-                    self.randomize_colour_wipe();
-                }
-            }
+        for _ in 0..self.pace.steps(dt, self.end) {
+            self.step();
         }
         Some(len)
     }
-
-    fn pixel_count(&self) -> usize {
-        N
-    }
 }
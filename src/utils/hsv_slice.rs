@@ -0,0 +1,129 @@
+use crate::{math8::scale8, RGB8};
+use palette::{Hsv, Srgb};
+
+/// Fully saturated, full brightness colours for 256 evenly spaced hues.
+///
+/// Saturation and value are applied with [scale8] on lookup, so converting through the table
+/// needs no trigonometry or divisions. Hue resolution is 1/256 of a turn (~1.4 degrees), which
+/// puts each channel within 6 of [palette]'s conversion.
+#[derive(Debug, Clone)]
+pub struct HueLut {
+    table: [[u8; 3]; 256],
+}
+
+/// A ready-built [HueLut], stored in flash on embedded targets.
+pub const HUE_LUT: HueLut = HueLut::new();
+
+impl HueLut {
+    pub const fn new() -> Self {
+        let mut table = [[0u8; 3]; 256];
+        let mut i = 0;
+        while i < 256 {
+            let h6 = i * 6;
+            let rising = (h6 & 0xff) as u8;
+            let falling = 255 - rising;
+            table[i] = match h6 >> 8 {
+                0 => [255, rising, 0],
+                1 => [falling, 255, 0],
+                2 => [0, 255, rising],
+                3 => [0, falling, 255],
+                4 => [rising, 0, 255],
+                _ => [255, 0, falling],
+            };
+            i += 1;
+        }
+        Self { table }
+    }
+
+    /// Look up an 8-bit hue/saturation/value.
+    #[inline]
+    pub fn get(&self, hue: u8, saturation: u8, value: u8) -> RGB8 {
+        let [r, g, b] = self.table[hue as usize];
+        let shade = |c: u8| scale8(255 - scale8(255 - c, saturation), value);
+        RGB8 {
+            r: shade(r),
+            g: shade(g),
+            b: shade(b),
+        }
+    }
+
+    /// Convert an [Hsv] colour through the table.
+    #[inline]
+    pub fn hsv(&self, hsv: Hsv) -> RGB8 {
+        let hue = (hsv.hue.into_positive_degrees() * (256.0 / 360.0)) as usize & 0xff;
+        self.get(
+            hue as u8,
+            (hsv.saturation * 255.0) as u8,
+            (hsv.value * 255.0) as u8,
+        )
+    }
+}
+
+impl Default for HueLut {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Branchless HSV to sRGB, using the `min`/`max` form of the hexcone model.
+///
+/// Matches [palette]'s conversion to within float rounding, but compiles to straight-line
+/// code that the optimiser can vectorise across a slice.
+#[inline]
+fn hsv_to_srgb_fast(hsv: Hsv) -> Srgb {
+    let h = hsv.hue.into_positive_degrees() / 60.0;
+    let vs = hsv.value * hsv.saturation;
+    let channel = |n: f32| {
+        let mut k = n + h;
+        if k >= 6.0 {
+            k -= 6.0;
+        }
+        hsv.value - vs * k.min(4.0 - k).clamp(0.0, 1.0)
+    };
+    Srgb::new(channel(5.0), channel(3.0), channel(1.0))
+}
+
+/// Convert a slice of [Hsv] colours to [Srgb]. Converts `min(src.len(), dst.len())` pixels.
+pub fn hsv_slice_to_srgb(src: &[Hsv], dst: &mut [Srgb]) {
+    for (out, hsv) in dst.iter_mut().zip(src) {
+        *out = hsv_to_srgb_fast(*hsv);
+    }
+}
+
+/// Convert a slice of [Hsv] colours to [RGB8], optionally through a [HueLut].
+/// Converts `min(src.len(), dst.len())` pixels.
+pub fn hsv_slice_to_rgb8(src: &[Hsv], dst: &mut [RGB8], lut: Option<&HueLut>) {
+    match lut {
+        Some(lut) => {
+            for (out, hsv) in dst.iter_mut().zip(src) {
+                *out = lut.hsv(*hsv);
+            }
+        }
+        None => {
+            let to_u8 = |c: f32| (c * 255.0 + 0.5) as u8;
+            for (out, hsv) in dst.iter_mut().zip(src) {
+                let c = hsv_to_srgb_fast(*hsv);
+                *out = RGB8 {
+                    r: to_u8(c.red),
+                    g: to_u8(c.green),
+                    b: to_u8(c.blue),
+                };
+            }
+        }
+    }
+}
+
+/// Render `buf` from a per-pixel HSV function, converting in stack-sized chunks through
+/// [crate::Pixel::from_hsv_slice] so the batched conversion is used without an `N` sized array.
+/// The `int-math` path renders from 8-bit colours instead.
+#[cfg(not(feature = "int-math"))]
+pub(crate) fn render_hsv<P: crate::Pixel>(buf: &mut [P], mut colour: impl FnMut(usize) -> Hsv) {
+    const CHUNK: usize = 32;
+    let mut hsv = [Hsv::new(0.0, 0.0, 0.0); CHUNK];
+    for (c, out) in buf.chunks_mut(CHUNK).enumerate() {
+        for (j, slot) in hsv.iter_mut().enumerate().take(out.len()) {
+            *slot = colour(c * CHUNK + j);
+        }
+        P::from_hsv_slice(&hsv[..out.len()], out);
+    }
+}
@@ -10,5 +10,3 @@ pub fn hsv_to_rgb8_pixel(hsv: Hsv) -> RGB8 {
         b: srgb8.blue,
     }
 }
-
-// (Intentionally minimal: slice conversion helper removed.)
@@ -1,5 +1,11 @@
+//! Colour conversion helpers shared by the effects.
+
+mod hsv_slice;
 mod hsv_to_srgb;
 mod srgbu8_to_hsv;
 
+#[cfg(not(feature = "int-math"))]
+pub(crate) use hsv_slice::render_hsv;
+pub use hsv_slice::{hsv_slice_to_rgb8, hsv_slice_to_srgb, HueLut, HUE_LUT};
 pub use hsv_to_srgb::hsv_to_rgb8_pixel;
 pub use srgbu8_to_hsv::rgb8_to_hsv;
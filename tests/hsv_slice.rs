@@ -0,0 +1,40 @@
+use palette::Hsv;
+use smart_led_effects::{
+    utils::{hsv_slice_to_rgb8, hsv_to_rgb8_pixel, HueLut, HUE_LUT},
+    RGB8,
+};
+
+/// Largest channel difference from the palette conversion over every hue, in tenths of a
+/// degree, at a few saturations and values.
+fn max_error(lut: Option<&HueLut>) -> u8 {
+    let mut worst = 0;
+    for &saturation in &[0.0, 0.25, 0.5, 1.0] {
+        for &value in &[0.1, 0.5, 0.8, 1.0] {
+            let src: Vec<Hsv> = (0..3600)
+                .map(|h| Hsv::new(h as f32 / 10.0, saturation, value))
+                .collect();
+            let mut dst = vec![RGB8::default(); src.len()];
+            hsv_slice_to_rgb8(&src, &mut dst, lut);
+            for (hsv, out) in src.iter().zip(&dst) {
+                let exact = hsv_to_rgb8_pixel(*hsv);
+                let error = [
+                    exact.r.abs_diff(out.r),
+                    exact.g.abs_diff(out.g),
+                    exact.b.abs_diff(out.b),
+                ];
+                worst = worst.max(error.into_iter().max().unwrap_or(0));
+            }
+        }
+    }
+    worst
+}
+
+#[test]
+fn slice_matches_palette() {
+    assert!(max_error(None) <= 1);
+}
+
+#[test]
+fn hue_lut_error_bound() {
+    assert!(max_error(Some(&HUE_LUT)) <= 6);
+}
@@ -0,0 +1,106 @@
+//! Boundary values from FastLED's C `lib8tion` (with `FASTLED_SCALE8_FIXED`), so a change to
+//! the port cannot silently change its rounding.
+
+use smart_led_effects::{math8::*, RGB8};
+
+const EDGES: [u8; 4] = [0, 127, 128, 255];
+
+/// `f(i, j)` for every pair of edge values, row by row.
+fn table(f: impl Fn(u8, u8) -> u8) -> [[u8; 4]; 4] {
+    EDGES.map(|i| EDGES.map(|j| f(i, j)))
+}
+
+#[test]
+fn scale() {
+    let expected = [[0, 0, 0, 0], [0, 63, 63, 127], [0, 64, 64, 128], [0, 127, 128, 255]];
+    assert_eq!(table(scale8), expected);
+    let expected = [[0, 0, 0, 0], [0, 64, 64, 127], [0, 64, 65, 128], [0, 127, 128, 255]];
+    assert_eq!(table(scale8_video), expected);
+    assert_eq!(scale8_rgb(RGB8::new(255, 128, 127), 128), RGB8::new(128, 64, 63));
+}
+
+#[test]
+fn saturating() {
+    let expected = [[0, 127, 128, 255], [127, 254, 255, 255], [128, 255, 255, 255], [255; 4]];
+    assert_eq!(table(qadd8), expected);
+    let expected = [[0, 0, 0, 0], [127, 0, 0, 0], [128, 1, 0, 0], [255, 128, 127, 0]];
+    assert_eq!(table(qsub8), expected);
+}
+
+#[test]
+fn lerp() {
+    let expected = [[0, 63, 64, 128], [64, 127, 127, 191], [64, 128, 128, 191], [127, 191, 192, 255]];
+    assert_eq!(table(|a, b| lerp8by8(a, b, 128)), expected);
+    assert_eq!(lerp8by8(10, 200, 0), 10);
+    assert_eq!(lerp8by8(10, 200, 255), 200);
+}
+
+#[test]
+fn sine() {
+    let theta = [0, 32, 64, 96, 127, 128, 192, 255];
+    assert_eq!(theta.map(sin8), [128, 218, 255, 218, 131, 128, 1, 125]);
+    assert_eq!(EDGES.map(cos8), [255, 2, 1, 254]);
+}
+
+#[test]
+fn ease() {
+    assert_eq!(EDGES.map(ease8_in_out_quad), [0, 126, 129, 255]);
+    assert_eq!(EDGES.map(ease8_in_out_cubic), [0, 127, 128, 255]);
+    // not in FastLED: the ends are exact, the middle within the sine approximation
+    assert_eq!(ease16_in_out_sine(0), 0);
+    assert_eq!(ease16_in_out_sine(u16::MAX), u16::MAX);
+    assert!(ease16_in_out_sine(32768).abs_diff(32768) < 330);
+}
+
+#[test]
+fn beats() {
+    assert_eq!([0, 250, 500, 999, 1000].map(|t| beat8(60, t)), [0, 64, 128, 0, 0]);
+    assert_eq!(beat16(120, 250), 32812);
+    assert_eq!(beat88(120 << 8, 250), 32812);
+    assert_eq!([0, 250, 500, 750].map(|t| beatsin8(60, 10, 200, t, 0)), [105, 200, 105, 10]);
+}
+
+#[test]
+fn rainbow() {
+    let cases = [
+        ((0, 255, 255), (255, 0, 0)),
+        ((32, 255, 255), (171, 85, 0)),
+        ((64, 255, 255), (171, 170, 0)),
+        ((96, 255, 255), (0, 255, 0)),
+        ((128, 255, 255), (0, 171, 85)),
+        ((160, 255, 255), (0, 0, 255)),
+        ((192, 255, 255), (85, 0, 171)),
+        ((224, 255, 255), (170, 0, 85)),
+        ((255, 255, 255), (253, 0, 2)),
+        ((0, 0, 128), (65, 65, 65)),
+        ((0, 128, 128), (65, 16, 16)),
+        ((96, 255, 127), (0, 64, 0)),
+        ((0, 255, 0), (0, 0, 0)),
+    ];
+    for ((h, s, v), (r, g, b)) in cases {
+        assert_eq!(hsv2rgb_rainbow(Hsv8::new(h, s, v)), RGB8::new(r, g, b), "{h} {s} {v}");
+    }
+}
+
+#[test]
+fn spectrum() {
+    let cases = [
+        ((0, 255, 255), (251, 0, 0)),
+        ((85, 255, 255), (0, 251, 0)),
+        ((170, 255, 255), (0, 0, 251)),
+        ((255, 255, 255), (251, 0, 0)),
+        ((0, 0, 255), (254, 254, 254)),
+        ((0, 128, 128), (126, 63, 63)),
+    ];
+    for ((h, s, v), (r, g, b)) in cases {
+        assert_eq!(hsv2rgb_spectrum(Hsv8::new(h, s, v)), RGB8::new(r, g, b), "{h} {s} {v}");
+    }
+}
+
+#[test]
+fn random() {
+    let mut rng = Random8::new(0);
+    assert_eq!([(); 3].map(|_| rng.random16()), [13849, 3222, 9479]);
+    let mut rng = Random8::new(42);
+    assert_eq!([(); 4].map(|_| rng.random8()), [113, 224, 50, 147]);
+}